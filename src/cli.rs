@@ -0,0 +1,1136 @@
+use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{ignore::IgnoreRule, mapping::KeyMapping, pattern};
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    after_help = "Exit codes:\n  \
+                  0  no differences found\n  \
+                  1  differences, conflicts or rule violations found\n  \
+                  2  invalid arguments\n  \
+                  3  an input file could not be read or parsed\n  \
+                  4  a Consul request failed\n  \
+                  5  an output could not be written\n  \
+                  6  the --deadline was exceeded"
+)]
+pub struct Args {
+    #[command(flatten)]
+    pub global: GlobalOpts,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// Options shared by every subcommand
+#[derive(ClapArgs, Debug)]
+pub struct GlobalOpts {
+    /// Host of the Consul agent, optionally with a scheme and a port such as
+    /// `https://consul.internal:8501`
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "CONSUL_HTTP_ADDR",
+        default_value = "localhost"
+    )]
+    pub consul_host: String,
+
+    /// Traces each key read on stderr, repeat to log the requests to Consul too
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub debug: u8,
+
+    /// Prints nothing but errors, leaving the result to the exit status
+    #[arg(short, long, global = true, conflicts_with = "debug")]
+    pub quiet: bool,
+
+    /// When to color the diff
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Order the differences are listed in
+    #[arg(long, global = true, value_enum, default_value_t = SortOrder::Key)]
+    pub sort: SortOrder,
+
+    /// Gives up on the whole command once it has run this long, retries
+    /// included, and exits with 6
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    pub deadline: Option<Duration>,
+
+    #[command(flatten)]
+    pub connection: ConnectionOpts,
+
+    #[command(flatten)]
+    pub input: InputOpts,
+
+    #[command(flatten)]
+    pub compare: CompareOpts,
+}
+
+// Options for connecting to Consul, shared by every client of a command
+#[derive(ClapArgs, Debug, Clone)]
+#[command(next_help_heading = "Consul connection")]
+pub struct ConnectionOpts {
+    /// Port of the Consul API, unless the host has one
+    #[arg(long, global = true, default_value_t = 8500)]
+    pub consul_port: u16,
+
+    /// Datacenter the KV requests target, defaults to the datacenter of the
+    /// agent
+    #[arg(long, global = true, value_name = "DC")]
+    pub datacenter: Option<String>,
+
+    /// Consul Enterprise namespace every request is made in
+    #[arg(long, global = true, env = "CONSUL_NAMESPACE")]
+    pub consul_namespace: Option<String>,
+
+    /// Consul Enterprise admin partition every request is made in
+    #[arg(long, global = true, env = "CONSUL_PARTITION")]
+    pub partition: Option<String>,
+
+    /// Consistency mode of KV reads. Stale reads are answered by any server,
+    /// consistent ones only once the leader confirms it still leads
+    #[arg(long, global = true, value_enum, default_value_t = Consistency::Default)]
+    pub consistency: Consistency,
+
+    /// Times a request is sent again after a transient failure, such as a
+    /// 5xx answer or a broken connection
+    #[arg(long, global = true, default_value_t = 0)]
+    pub retries: u32,
+
+    /// Wait before the first retry, doubled before each one after it
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        default_value = "500ms",
+        value_parser = parse_duration
+    )]
+    pub retry_backoff: Duration,
+
+    /// Fails each HTTP request to Consul that takes longer than this. Keep it
+    /// above the --wait of watch, which blocks for that long
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// ACL token sent with every request
+    #[arg(long, global = true, env = "CONSUL_HTTP_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Reads the ACL token from this file, unless --token is given
+    #[arg(
+        long,
+        global = true,
+        env = "CONSUL_HTTP_TOKEN_FILE",
+        value_name = "FILE"
+    )]
+    pub token_file: Option<PathBuf>,
+
+    /// Scheme of the Consul API, unless the host has one. Defaults to https
+    /// when CONSUL_HTTP_SSL is true and http otherwise
+    #[arg(long, global = true, value_enum)]
+    pub scheme: Option<Scheme>,
+
+    /// PEM encoded CA certificate the Consul server certificate is verified
+    /// against, besides the system ones
+    #[arg(long, global = true, env = "CONSUL_CACERT", value_name = "FILE")]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Accepts any server certificate, as when CONSUL_HTTP_SSL_VERIFY is
+    /// false
+    #[arg(long, global = true)]
+    pub tls_skip_verify: bool,
+
+    /// PEM encoded client certificate presented to Consul agents that verify
+    /// incoming connections
+    #[arg(
+        long,
+        global = true,
+        env = "CONSUL_CLIENT_CERT",
+        value_name = "FILE",
+        requires = "client_key"
+    )]
+    pub client_cert: Option<PathBuf>,
+
+    /// PEM encoded RSA or PKCS#8 private key of the client certificate
+    #[arg(
+        long,
+        global = true,
+        env = "CONSUL_CLIENT_KEY",
+        value_name = "FILE",
+        requires = "client_cert"
+    )]
+    pub client_key: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Consistency {
+    Default,
+    Consistent,
+    Stale,
+}
+
+// Options controlling how input property files are read
+#[derive(ClapArgs, Debug, Clone)]
+pub struct InputOpts {
+    /// Format of the input files, detected from the file extension by default
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<InputFormat>,
+
+    /// Separator between the path segments of a .env variable name, so
+    /// `FOO_BAR` is compared as `foo/bar`
+    #[arg(long, global = true, default_value = "_")]
+    pub env_separator: String,
+
+    /// Prefix stripped from the keys of a `consul kv export`, defaults to the
+    /// deepest folder all of its keys share
+    #[arg(long, global = true)]
+    pub export_prefix: Option<String>,
+
+    /// Compares ConfigMap entries named like config files, such as
+    /// `application.yml`, by the properties they contain
+    #[arg(long, global = true)]
+    pub parse_embedded: bool,
+
+    /// Active Spring profile, repeat for several. Only the YAML documents
+    /// without a profile selector or matching one of these, or `default`
+    /// when none is given, are compared, merged with the profile specific
+    /// files such as `application-prod.yml` next to each input file
+    #[arg(long, global = true)]
+    pub profile: Vec<String>,
+
+    /// Column delimiter of CSV input
+    #[arg(long, global = true, default_value = ",", value_parser = parse_delimiter)]
+    pub delimiter: u8,
+
+    /// Overrides input keys after reading, in Helm's `--set a.b=c,d=e` syntax.
+    /// Repeat for several
+    #[arg(long, global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// How lists in the input files are laid out as Consul keys
+    #[arg(long, global = true, value_enum, default_value_t = ArrayStyle::Indexed)]
+    pub array_style: ArrayStyle,
+
+    /// Compares the mappings and lists nested this deep in the input files as
+    /// single JSON values, the way they are stored as blobs in Consul
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_depth: Option<u32>,
+
+    /// Compares the mapping or list at the keys matching this glob or `re:`
+    /// regex as a single JSON value, such as `logging/pattern`. May be
+    /// repeated
+    #[arg(long, global = true, value_name = "PATTERN", value_parser = pattern::parse_key_pattern)]
+    pub opaque: Vec<Regex>,
+
+    /// What null values in the input files become
+    #[arg(long, global = true, value_enum, default_value_t = NullPolicy::Literal)]
+    pub null_policy: NullPolicy,
+
+    /// Separator placed between the path segments of input keys, such as `.`
+    /// for Consul trees holding Spring style `server.port` keys
+    #[arg(long, global = true, default_value = "/")]
+    pub key_separator: String,
+
+    /// Fails when a properties, .env, INI or CSV file sets a key more than
+    /// once, instead of warning and keeping the last value. Duplicate YAML
+    /// keys always fail
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// JSON Schema, in JSON or YAML, that YAML and JSON input files must
+    /// validate against before they are compared
+    #[arg(long, global = true, value_name = "SCHEMA")]
+    pub schema: Option<PathBuf>,
+
+    /// Character encoding of the input files. Java historically wrote
+    /// .properties files in ISO-8859-1
+    #[arg(long, global = true, value_enum, default_value_t = Encoding::Utf8)]
+    pub encoding: Encoding,
+}
+
+// Options controlling how keys and values are matched up when comparing
+#[derive(ClapArgs, Debug, Clone)]
+pub struct CompareOpts {
+    /// Treats `.` and `/` in keys as the same separator on both sides, so
+    /// `spring.datasource.url` matches `spring/datasource/url`
+    #[arg(long, global = true)]
+    pub spring_compat: bool,
+
+    /// Matches keys the way Spring's relaxed binding does, ignoring case,
+    /// dashes and underscores, so `maxConnections`, `max-connections` and
+    /// `max_connections` are the same key
+    #[arg(long, global = true)]
+    pub relaxed_binding: bool,
+
+    /// Matches keys regardless of their casing, so `Datasource/Url` and
+    /// `datasource/url` are the same key, warning of the mismatch
+    #[arg(long, global = true)]
+    pub ignore_key_case: bool,
+
+    /// Only compares the keys matching this glob, such as `datasource/**`,
+    /// or regex prefixed with `re:`. May be repeated
+    #[arg(long, global = true, value_name = "PATTERN", value_parser = pattern::parse_key_pattern)]
+    pub include: Vec<Regex>,
+
+    /// Leaves out the keys matching this glob or `re:` regex. May be repeated
+    #[arg(long, global = true, value_name = "PATTERN", value_parser = pattern::parse_key_pattern)]
+    pub exclude: Vec<Regex>,
+
+    /// How values are compared
+    #[arg(long, global = true, value_enum, default_value_t = ValueTypes::Strict)]
+    pub value_types: ValueTypes,
+
+    /// Treats numbers as equal when they differ by at most this much, such as
+    /// `0.3` and `0.30000000000000004`
+    #[arg(long, global = true, value_name = "DELTA", value_parser = parse_tolerance)]
+    pub float_tolerance: Option<f64>,
+
+    /// How keys with an empty value in Consul are compared
+    #[arg(long, global = true, value_enum, default_value_t = EmptyValues::Present)]
+    pub empty_values: EmptyValues,
+
+    /// Reports the keys whose file value holds a `${...}` or `{{ ... }}`
+    /// placeholder as unresolvable rather than changed
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    pub placeholders: Option<Placeholders>,
+
+    /// Compares comma separated values and the elements of flattened lists,
+    /// such as `servers[0]`, regardless of their order
+    #[arg(long, global = true)]
+    pub unordered_lists: bool,
+
+    /// Normalizes both values before comparing them, with every step when
+    /// none is given
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "STEPS",
+        num_args = 0..=1,
+        value_delimiter = ',',
+        default_missing_value = "all"
+    )]
+    pub normalize: Vec<Normalization>,
+
+    /// Lists the keys whose differences are expected, one glob or `re:`
+    /// regex per line, reported as ignored. `when REGEX` after a key only
+    /// ignores values that both match it with the same captures. Defaults to
+    /// `.consulhelpignore` when it exists
+    #[arg(long, global = true, value_name = "FILE")]
+    pub ignore_file: Option<PathBuf>,
+
+    /// The rules read from the ignore file
+    #[arg(skip)]
+    pub ignore_rules: Vec<IgnoreRule>,
+
+    /// Rewrites the file keys into the Consul layout before comparing, one
+    /// `FROM -> TO` rule per line, such as `legacy/db/* -> datasource/*` or
+    /// `re:^legacy/(.+)$ -> app/$1`
+    #[arg(long, global = true, value_name = "FILE")]
+    pub key_map: Option<PathBuf>,
+
+    /// The rules read from the key mapping file
+    #[arg(skip)]
+    pub key_mappings: Vec<KeyMapping>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmptyValues {
+    /// As keys set to an empty value, as Spring reads them
+    Present,
+    /// As keys that are not set
+    Missing,
+    /// Reported apart, whatever the file holds
+    Separate,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placeholders {
+    /// Leaves the placeholders as they are
+    Report,
+    /// Resolves `${NAME}` and `${NAME:default}` from the environment first
+    Resolve,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueTypes {
+    /// As strings, so `8080.0` and `8080` differ
+    Strict,
+    /// Numbers and booleans in their canonical forms, so `8080.0` and `8080`
+    /// or `True` and `true` are the same
+    Coerce,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Strips the whitespace around the value
+    Trim,
+    /// Strips a pair of single or double quotes around the value
+    Dequote,
+    /// Turns CRLF line endings into LF
+    Newlines,
+    /// Every step above
+    All,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when printing to a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// By key, with the kinds of differences mixed in the formats listing a
+    /// row per key
+    Key,
+    /// By kind of difference, and then by key
+    Status,
+    /// In the order of the input files and then of Consul
+    None,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputFormat {
+    Yaml,
+    Json,
+    /// Java `.properties`, with dotted keys mapped to slash separated paths
+    Properties,
+    Toml,
+    /// Dotenv `KEY=VALUE` files, with variable names split on --env-separator
+    Dotenv,
+    /// The JSON written by `consul kv export`, also detected in .json files
+    ConsulExport,
+    /// HCL, with blocks nested under their type and labels
+    Hcl,
+    /// A Kubernetes ConfigMap manifest, also detected in YAML files
+    ConfigMap,
+    /// INI, with keys placed under their section
+    Ini,
+    /// XML, either Java XML properties or elements and attributes as key paths
+    Xml,
+    /// Two column `key,value` CSV
+    Csv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// One key per element, `list[0]`, `list[1]`
+    Indexed,
+    /// A single key holding the elements joined by commas, as Spring Cloud
+    /// Consul reads lists
+    Comma,
+    /// A single key holding the list as JSON
+    Json,
+    /// One key per element with the index as a path segment, `list/0`
+    Explode,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Leaves the key out, as if it were not set
+    Skip,
+    /// An empty value
+    Empty,
+    /// The string `null`
+    Literal,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    /// Latin-1, where every byte is the character of the same code point
+    #[value(name = "iso-8859-1", alias = "latin1")]
+    Latin1,
+}
+
+fn parse_delimiter(value: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        [delimiter] => Ok(*delimiter),
+        _ if value == "\\t" => Ok(b'\t'),
+        _ => Err(format!(
+            "invalid delimiter `{}`, expected a single ASCII character",
+            value
+        )),
+    }
+}
+
+/// Parses a duration such as `500ms`, `10s`, `5m` or `1h`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    match (amount.parse::<u64>(), unit) {
+        (Ok(amount), "ms") => Ok(Duration::from_millis(amount)),
+        (Ok(amount), "s") => Ok(Duration::from_secs(amount)),
+        (Ok(amount), "m") => Ok(Duration::from_secs(amount.saturating_mul(60))),
+        (Ok(amount), "h") => Ok(Duration::from_secs(amount.saturating_mul(3600))),
+        _ => Err(format!(
+            "invalid duration `{}`, expected a number followed by ms, s, m or h",
+            value
+        )),
+    }
+}
+
+fn parse_tolerance(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(tolerance) if tolerance.is_finite() && tolerance >= 0.0 => Ok(tolerance),
+        _ => Err(format!(
+            "invalid tolerance `{}`, expected a non-negative number",
+            value
+        )),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Prints the Consul keys that differ from the input property file or another prefix
+    Diff(DiffArgs),
+    /// Exports the keys under the app prefix into a nested YAML file
+    Pull(PullArgs),
+    /// Writes the keys missing or changed in Consul from the input property file
+    Push(PushArgs),
+    /// Exports the keys under the app prefix, or the input property file, as
+    /// flat key=value lines or a manifest for another tool
+    Export(ExportArgs),
+    /// Reconciles the input property file and Consul in both directions
+    Sync(SyncArgs),
+    /// Deletes the Consul keys that are not present in the input property file
+    DeleteOrphans(DeleteOrphansArgs),
+    /// Re-runs the diff every time the keys under the app prefix change
+    Watch(WatchArgs),
+    /// Copies the keys under one prefix to another prefix or Consul cluster
+    Copy(CopyArgs),
+    /// Prints the value of a single key under the app prefix
+    Get(GetArgs),
+    /// Sets the value of a single key under the app prefix
+    Set(SetArgs),
+    /// Prints the keys under the app prefix as an indented hierarchy
+    Tree(TreeArgs),
+    /// Moves every key under one prefix to another in a single Consul transaction
+    RenamePrefix(RenamePrefixArgs),
+    /// Runs the diff for every application listed in a manifest file
+    Batch(BatchArgs),
+    /// Checks the input property file against a rules file
+    Validate(ValidateArgs),
+    /// Saves the keys under the app prefix to a timestamped snapshot file
+    Snapshot(SnapshotArgs),
+    /// Writes the keys of a snapshot file back to Consul
+    Restore(RestoreArgs),
+    /// Generates a commented application YAML from the keys under the app prefix
+    Init(InitArgs),
+    /// Checks the Consul agent is reachable and the app prefix can be read
+    Doctor(DoctorArgs),
+    /// Saves the changes a push would make to a plan file for later review
+    Plan(PlanArgs),
+    /// Applies a plan file, failing if Consul changed since it was made
+    Apply(ApplyArgs),
+    /// Lists the folder keys under the app prefix with no keys below them and
+    /// optionally deletes them
+    Vacuum(VacuumArgs),
+    /// Lists the distinct keys holding the same value, in Consul or the input files
+    Duplicates(DuplicatesArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+#[command(group(
+    ArgGroup::new("target")
+        .multiple(true)
+        .args(["input_property", "target_consul_host", "target_datacenter", "target_prefix"])
+))]
+pub struct DiffArgs {
+    /// Repeat to compare several prefixes in a single run, or use `*` and `?`
+    /// wildcards to match the folders in Consul
+    #[arg(short, long, required_unless_present = "compare_with")]
+    pub app_prefix: Vec<String>,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY", conflicts_with_all = ["target_consul_host", "target_datacenter", "target_prefix"])]
+    pub input_property: Vec<PathBuf>,
+
+    /// Compares against another Consul cluster instead of a file
+    #[arg(long)]
+    pub target_consul_host: Option<String>,
+
+    /// Datacenter to compare against on the target cluster, defaults to
+    /// --datacenter
+    #[arg(long, value_name = "DC")]
+    pub target_datacenter: Option<String>,
+
+    /// Prefix to compare against on the target cluster, defaults to --app-prefix
+    #[arg(long)]
+    pub target_prefix: Option<String>,
+
+    /// Compares the input property file against this file instead of Consul
+    #[arg(long, value_name = "FILE", requires = "input_property")]
+    pub compare_with: Option<PathBuf>,
+
+    /// Reads the input property file from this git revision instead of the working tree
+    #[arg(long, value_name = "REV", requires = "input_property")]
+    pub git_ref: Option<String>,
+
+    /// Compares the properties held in this single key under the app prefix,
+    /// such as `application.yml` in Spring Cloud Consul's FILES format,
+    /// instead of the keys under the app prefix
+    #[arg(long, value_name = "KEY", conflicts_with = "compare_with")]
+    pub blob_key: Option<String>,
+
+    /// Format of the report. Other formats than text are written to the
+    /// output file, or to stdout without one
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    /// Renders the differences through this Tera template instead of an
+    /// output format, written to the output file or to stdout without one
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["output_format", "summary_only", "group"])]
+    pub output_template: Option<PathBuf>,
+
+    /// Prints only the summary counts of the text report, without the keys
+    #[arg(long)]
+    pub summary_only: bool,
+
+    /// Groups the text report by the first folder of each key, such as
+    /// `datasource` or `kafka`, with the counts of each group
+    #[arg(long)]
+    pub group: bool,
+
+    /// With the kv-import and terraform formats, sets every key in the file
+    /// rather than only the differences. Terraform then manages the whole
+    /// prefix with a `consul_key_prefix` resource, deleting the keys only in
+    /// Consul
+    #[arg(long)]
+    pub full_state: bool,
+
+    /// Reports a key only in Consul and a key only in the file holding the
+    /// same value as a probable rename, with the text and json formats
+    #[arg(long, conflicts_with = "output_template")]
+    pub detect_renames: bool,
+
+    /// Only exits with differences when there are more than this many of the
+    /// kinds --fail-on counts, across every prefix
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub fail_threshold: usize,
+
+    /// The kinds of differences that count towards --fail-threshold
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "any")]
+    pub fail_on: Vec<FailOn>,
+
+    /// Gives key patterns a severity, one `PATTERN = SEVERITY` per line such
+    /// as `datasource/** = critical`, grouping the text report by severity.
+    /// Keys no pattern matches are info
+    #[arg(long, value_name = "FILE", conflicts_with = "group")]
+    pub severity_file: Option<PathBuf>,
+
+    /// Only counts the differences of at least this severity towards
+    /// --fail-threshold
+    #[arg(long, value_enum, value_name = "SEVERITY", requires = "severity_file")]
+    pub fail_on_severity: Option<Severity>,
+
+    /// With the text format, receives the Consul side of the differences as
+    /// key=value lines
+    #[arg(short, long, value_name = "OUTPUT FILE")]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailOn {
+    /// Keys only in the file
+    Added,
+    /// Keys only in Consul
+    Removed,
+    /// Keys with different values
+    Changed,
+    /// Every kind of difference
+    Any,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The differences listed by kind
+    Text,
+    /// An object with `only_in_file`, `only_in_consul` and `changed` arrays
+    Json,
+    /// The file values of the keys missing or changed in Consul, nested
+    Yaml,
+    /// A unified diff from the Consul keys to the file keys
+    Diff,
+    /// Aligned key, Consul value, file value and status columns
+    Table,
+    /// A summary and a table of the differences, for pull requests and tickets
+    Markdown,
+    /// `key,status,consul_value,file_value` rows for spreadsheets
+    Csv,
+    /// A standalone HTML page with collapsible sections and a search box
+    Html,
+    /// JUnit XML with a test suite per app prefix and a test case per key
+    Junit,
+    /// GitHub Actions warnings on the input file lines that set the keys
+    Github,
+    /// A Terraform `consul_keys` resource setting the keys to their file values
+    Terraform,
+    /// A shell script of `consul kv put` commands setting the file values
+    Script,
+    /// The `key`, `flags` and base64 `value` array `consul kv import` reads
+    KvImport,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct PullArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    #[arg(short, long, value_name = "OUTPUT FILE")]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct PushArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// Prints the changes that would be made without writing to Consul
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Asks for confirmation before applying each change
+    #[arg(long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct ExportArgs {
+    #[arg(short, long, required_unless_present = "input_property")]
+    pub app_prefix: Option<String>,
+
+    /// Exports these files, layered, instead of the keys in Consul
+    #[arg(
+        short,
+        long,
+        value_name = "INPUT PROPERTY",
+        conflicts_with = "app_prefix"
+    )]
+    pub input_property: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = ExportFormat::Properties)]
+    pub output_format: ExportFormat,
+
+    /// Name of the Kubernetes object, defaults to the app prefix or the name
+    /// of the first input file
+    #[arg(long, help_heading = "Kubernetes")]
+    pub name: Option<String>,
+
+    /// Namespace of the Kubernetes object
+    #[arg(long, help_heading = "Kubernetes")]
+    pub namespace: Option<String>,
+
+    /// Label of the Kubernetes object, may be repeated
+    #[arg(long, help_heading = "Kubernetes", value_name = "KEY=VALUE", value_parser = parse_label)]
+    pub label: Vec<(String, String)>,
+
+    /// Regular expression the keys exported into a Secret must match
+    #[arg(
+        long,
+        value_name = "REGEX",
+        default_value = "(?i)(password|passwd|secret|token|credential|private)"
+    )]
+    pub secret_pattern: String,
+
+    /// Writes the Secret values in plain text under `stringData` instead of
+    /// base64 encoded under `data`
+    #[arg(long)]
+    pub string_data: bool,
+
+    /// Prefix of the Ansible variable names, such as `myapp_`
+    #[arg(long, value_name = "PREFIX", default_value = "")]
+    pub var_prefix: String,
+
+    #[arg(short, long, value_name = "OUTPUT FILE")]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Flat key=value lines
+    Properties,
+    /// A Kubernetes ConfigMap with the folders of each key joined by dots
+    ConfigMap,
+    /// A Kubernetes Secret of the keys matching --secret-pattern
+    Secret,
+    /// A Docker `--env-file` with the keys joined by --env-separator, such as
+    /// `A_B_C` for `a/b/c`
+    EnvFile,
+    /// Ansible `group_vars` with a variable per key, named after its folders
+    /// with --var-prefix first
+    Ansible,
+}
+
+fn parse_label(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid label `{}`, expected KEY=VALUE", value))
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct SyncArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// How to resolve keys whose value differs between the file and Consul
+    #[arg(short, long, value_enum, default_value_t = SyncStrategy::Fail)]
+    pub strategy: SyncStrategy,
+
+    /// Deletes the Consul keys that are not present in the input property file
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Prints the changes that would be made without writing to Consul
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Asks for confirmation before applying each change
+    #[arg(long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncStrategy {
+    /// Writes the file value to Consul
+    PreferFile,
+    /// Keeps the value already in Consul
+    PreferConsul,
+    /// Aborts the sync without writing anything
+    Fail,
+}
+
+#[derive(ClapArgs, Debug)]
+#[command(group(ArgGroup::new("guard").required(true).args(["confirm", "dry_run", "interactive"])))]
+pub struct DeleteOrphansArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// Deletes the orphan keys
+    #[arg(long)]
+    pub confirm: bool,
+
+    /// Prints the keys that would be deleted without writing to Consul
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Asks for confirmation before applying each change
+    #[arg(long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct WatchArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// Maximum duration of each blocking query, in Consul duration format
+    #[arg(long, default_value = "5m")]
+    pub wait: String,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct CopyArgs {
+    /// Prefix the keys are copied from
+    #[arg(long)]
+    pub source_prefix: String,
+
+    /// Consul host to copy from, defaults to --consul-host
+    #[arg(long)]
+    pub source_host: Option<String>,
+
+    /// Prefix the keys are copied to
+    #[arg(long)]
+    pub dest_prefix: String,
+
+    /// Consul host to copy to, defaults to the source host
+    #[arg(long)]
+    pub dest_host: Option<String>,
+
+    /// Rewrites FROM into TO in every copied key, may be repeated
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_rewrite)]
+    pub rewrite: Vec<(String, String)>,
+
+    /// Prints the changes that would be made without writing to Consul
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Asks for confirmation before applying each change
+    #[arg(long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+}
+
+fn parse_rewrite(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .ok_or_else(|| format!("invalid rewrite rule `{}`, expected FROM=TO", value))
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct GetArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Key relative to the app prefix
+    pub key: String,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct SetArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Key relative to the app prefix
+    pub key: String,
+
+    pub value: String,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct TreeArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Maximum number of levels to print below the app prefix
+    #[arg(long)]
+    pub depth: Option<usize>,
+
+    /// Values longer than this many characters are truncated
+    #[arg(long, default_value_t = 40)]
+    pub max_value_width: usize,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct RenamePrefixArgs {
+    /// Prefix the keys are moved from
+    #[arg(long)]
+    pub from: String,
+
+    /// Prefix the keys are moved to, where none of them may exist yet
+    #[arg(long)]
+    pub to: String,
+
+    /// Deletes the keys under the old prefix once they are copied
+    #[arg(long)]
+    pub delete_source: bool,
+
+    /// Splits a move larger than one transaction, of 64 keys or 32 with
+    /// --delete-source, into several. A failure partway then leaves the keys
+    /// split between both prefixes
+    #[arg(long)]
+    pub allow_split: bool,
+
+    /// Prints the changes that would be made without writing to Consul
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct BatchArgs {
+    /// YAML or TOML file listing the applications to compare, each with an
+    /// input_file or a list of them to layer
+    #[arg(short, long)]
+    pub manifest: PathBuf,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct ValidateArgs {
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// YAML file with the required, forbidden, patterns and types rules
+    #[arg(short, long)]
+    pub rules: PathBuf,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct SnapshotArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Defaults to `<app prefix>-<unix timestamp>.snapshot.json`
+    #[arg(short, long, value_name = "OUTPUT FILE")]
+    pub output_file: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct RestoreArgs {
+    pub snapshot: PathBuf,
+
+    /// Prefix to restore into, defaults to the prefix the snapshot was taken from
+    #[arg(long)]
+    pub target_prefix: Option<String>,
+
+    /// Deletes the keys under the target prefix that are not in the snapshot
+    #[arg(long)]
+    pub prune: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct InitArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    #[arg(
+        short,
+        long,
+        value_name = "OUTPUT FILE",
+        default_value = "application.yml"
+    )]
+    pub output_file: PathBuf,
+
+    /// Overwrites the output file if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct DoctorArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Also checks the token may write under the app prefix
+    #[arg(long)]
+    pub check_write: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct PlanArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Repeat to layer files, later ones overriding the keys of earlier ones.
+    /// Use `-`, or leave out when piping, to read from stdin
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// Also plans the deletion of keys that are only in Consul
+    #[arg(long)]
+    pub delete: bool,
+
+    #[arg(
+        short,
+        long,
+        value_name = "OUTPUT FILE",
+        default_value = "consul-help.plan.json"
+    )]
+    pub output_file: PathBuf,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct ApplyArgs {
+    pub plan_file: PathBuf,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct VacuumArgs {
+    #[arg(short, long)]
+    pub app_prefix: String,
+
+    /// Deletes the empty folder keys instead of only listing them
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Asks for confirmation before deleting each key
+    #[arg(long, requires = "delete")]
+    pub interactive: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+#[command(group(
+    ArgGroup::new("source")
+        .required(true)
+        .multiple(true)
+        .args(["app_prefix", "input_property"])
+))]
+pub struct DuplicatesArgs {
+    /// Looks for duplicates among the keys under this prefix
+    #[arg(short, long)]
+    pub app_prefix: Option<String>,
+
+    /// Looks for duplicates among the keys of these files, layered
+    #[arg(short, long, value_name = "INPUT PROPERTY")]
+    pub input_property: Vec<PathBuf>,
+
+    /// Leaves out the values shorter than this many characters, such as
+    /// `true` or `8080`, which are expected to repeat
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub min_length: usize,
+
+    /// Prints the shared values, which are only shown by their length
+    /// otherwise as they are often secrets
+    #[arg(long)]
+    pub show_values: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn defines_every_argument_once() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_durations_with_a_unit() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("1.5s").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("ms").is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    cli::{Args, Command},
+    consul,
+    error::{Error, Outcome, Result},
+    ignore, mapping,
+};
+
+mod apply;
+mod batch;
+mod copy;
+mod delete_orphans;
+mod diff;
+mod doctor;
+mod duplicates;
+mod export;
+mod get;
+mod init;
+mod plan;
+mod pull;
+mod push;
+mod rename_prefix;
+mod restore;
+mod set;
+mod snapshot;
+mod sync;
+mod tree;
+mod vacuum;
+mod validate;
+mod watch;
+
+pub async fn run(mut args: Args) -> Result<Outcome> {
+    args.global.compare.ignore_rules = ignore::load(args.global.compare.ignore_file.as_deref())?;
+    args.global.compare.key_mappings = mapping::load(args.global.compare.key_map.as_deref())?;
+    let client = consul::client(&args.global.consul_host, &args.global.connection)?;
+
+    let Some(deadline) = args.global.deadline else {
+        return dispatch(&client, &args).await;
+    };
+    tokio::time::timeout(deadline, dispatch(&client, &args))
+        .await
+        .map_err(|_| Error::Deadline(deadline))?
+}
+
+async fn dispatch(client: &consul::Client, args: &Args) -> Result<Outcome> {
+    match &args.command {
+        Command::Diff(command) => diff::run(client, &args.global, command).await,
+        Command::Pull(command) => pull::run(client, &args.global, command).await,
+        Command::Push(command) => push::run(client, &args.global, command).await,
+        Command::Export(command) => export::run(client, &args.global, command).await,
+        Command::Sync(command) => sync::run(client, &args.global, command).await,
+        Command::DeleteOrphans(command) => delete_orphans::run(client, &args.global, command).await,
+        Command::Watch(command) => watch::run(client, &args.global, command).await,
+        Command::Copy(command) => copy::run(client, &args.global, command).await,
+        Command::Get(command) => get::run(client, command).await,
+        Command::Set(command) => set::run(client, command).await,
+        Command::Tree(command) => tree::run(client, &args.global, command).await,
+        Command::RenamePrefix(command) => rename_prefix::run(client, &args.global, command).await,
+        Command::Batch(command) => batch::run(&args.global, command).await,
+        Command::Validate(command) => validate::run(&args.global, command),
+        Command::Snapshot(command) => snapshot::run(client, &args.global, command).await,
+        Command::Restore(command) => restore::run(client, command).await,
+        Command::Init(command) => init::run(client, &args.global, command).await,
+        Command::Doctor(command) => doctor::run(client, &args.global, command).await,
+        Command::Plan(command) => plan::run(client, &args.global, command).await,
+        Command::Apply(command) => apply::run(client, &args.global, command).await,
+        Command::Vacuum(command) => vacuum::run(client, &args.global, command).await,
+        Command::Duplicates(command) => duplicates::run(client, &args.global, command).await,
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    cli::{ApplyArgs, GlobalOpts},
+    consul::{self, MAX_TXN_OPERATIONS},
+    error::{Error, Outcome, Result},
+    output::outln,
+    plan::PlanFile,
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &ApplyArgs,
+) -> Result<Outcome> {
+    let plan_file = PlanFile::load(&args.plan_file)?;
+
+    if plan_file.consul_host != global.consul_host {
+        return Err(Error::Usage(format!(
+            "The plan was made against consul host {}, pass --consul-host {} to apply it.",
+            plan_file.consul_host, plan_file.consul_host
+        )));
+    }
+    let connection = &global.connection;
+    for (flag, planned, current) in [
+        (
+            "--datacenter",
+            &plan_file.datacenter,
+            &connection.datacenter,
+        ),
+        (
+            "--consul-namespace",
+            &plan_file.namespace,
+            &connection.consul_namespace,
+        ),
+        ("--partition", &plan_file.partition, &connection.partition),
+    ] {
+        if planned != current {
+            return Err(Error::Usage(match planned {
+                Some(planned) => format!(
+                    "The plan was made with {} {}, pass {} {} to apply it.",
+                    flag, planned, flag, planned
+                ),
+                None => format!(
+                    "The plan was made without {}, leave it out to apply it.",
+                    flag
+                ),
+            }));
+        }
+    }
+
+    let plan = plan_file.plan();
+    if plan.is_empty() {
+        outln!("No changes to apply.");
+        return Ok(Outcome::Clean);
+    }
+    plan.print();
+
+    // Checking every index up front keeps a stale plan from being applied in
+    // part when it spans more than one transaction
+    let pairs = consul::load_pairs(client, &plan_file.app_prefix).await?;
+    let stale_keys = plan_file.stale_keys(&consul::modify_indexes(&plan_file.app_prefix, &pairs));
+    if !stale_keys.is_empty() {
+        return Err(Error::Rejected(format!(
+            "Consul changed since the plan was made, keys: {}",
+            stale_keys.join(", ")
+        )));
+    }
+
+    for batch in plan_file.txn_operations().chunks(MAX_TXN_OPERATIONS) {
+        consul::transaction(client, batch).await?;
+    }
+    outln!(
+        "{} changes applied to {}.",
+        plan.operations.len(),
+        plan_file.app_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
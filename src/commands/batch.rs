@@ -0,0 +1,171 @@
+use serde::Deserialize;
+use std::{fs, path::PathBuf};
+use tokio::task::JoinSet;
+
+use crate::{
+    cli::{BatchArgs, CompareOpts, ConnectionOpts, GlobalOpts, InputOpts, OutputFormat},
+    consul,
+    diff::DiffResult,
+    error::{Error, Outcome, Result},
+    input,
+    output::{out, outln, progress},
+    report,
+};
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    apps: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ManifestEntry {
+    app_prefix: String,
+    input_file: InputFiles,
+    consul_host: Option<String>,
+}
+
+/// One input file, or a list layered the way repeated `-i` flags are
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum InputFiles {
+    One(PathBuf),
+    Layers(Vec<PathBuf>),
+}
+
+impl InputFiles {
+    fn paths(&self) -> &[PathBuf] {
+        match self {
+            InputFiles::One(path) => std::slice::from_ref(path),
+            InputFiles::Layers(paths) => paths,
+        }
+    }
+}
+
+pub async fn run(global: &GlobalOpts, args: &BatchArgs) -> Result<Outcome> {
+    let manifest = load_manifest(&args.manifest)?;
+    let base_dir = args
+        .manifest
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_default();
+
+    // An empty list would fall back to stdin like a missing -i does
+    if let Some(entry) = manifest
+        .apps
+        .iter()
+        .find(|entry| entry.input_file.paths().is_empty())
+    {
+        return Err(Error::read(
+            &args.manifest,
+            format!("{} lists no input_file", entry.app_prefix),
+        ));
+    }
+
+    let mut tasks = JoinSet::new();
+    for (position, entry) in manifest.apps.iter().cloned().enumerate() {
+        let consul_host = entry
+            .consul_host
+            .clone()
+            .unwrap_or_else(|| global.consul_host.clone());
+        let input_files: Vec<PathBuf> = entry
+            .input_file
+            .paths()
+            .iter()
+            .map(|path| base_dir.join(path))
+            .collect();
+        let input_opts = global.input.clone();
+        let compare_opts = global.compare.clone();
+        let connection = global.connection.clone();
+
+        tasks.spawn(async move {
+            let difference = compare(
+                &consul_host,
+                &entry.app_prefix,
+                &input_files,
+                &input_opts,
+                &compare_opts,
+                &connection,
+            )
+            .await;
+            (position, consul_host, difference)
+        });
+    }
+
+    let mut reports = tasks.join_all().await;
+    reports.sort_by_key(|(position, _, _)| *position);
+
+    let compared = reports.len();
+    let mut drifted = 0;
+    let mut first_error = None;
+    for (position, consul_host, difference) in reports {
+        outln!();
+        outln!(
+            "== {} ({}) ==",
+            manifest.apps[position].app_prefix,
+            consul_host
+        );
+        match difference {
+            Ok(result) if result.is_empty() => outln!("No differences found."),
+            Ok(mut result) => {
+                drifted += 1;
+                result.sort(global.sort);
+                out!(
+                    "{}",
+                    report::render(
+                        &result,
+                        OutputFormat::Text,
+                        global.color.enabled(),
+                        global.sort
+                    )?
+                );
+            }
+            Err(error) => {
+                outln!("Error: {}", error);
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    outln!();
+    outln!(
+        "{} applications compared, {} with differences.",
+        compared,
+        drifted
+    );
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(Outcome::drift_if(drifted > 0)),
+    }
+}
+
+async fn compare(
+    consul_host: &str,
+    app_prefix: &str,
+    input_files: &[PathBuf],
+    input_opts: &InputOpts,
+    compare_opts: &CompareOpts,
+    connection: &ConnectionOpts,
+) -> Result<DiffResult> {
+    let client = consul::client(consul_host, connection)?;
+    let consul_properties = consul::load_properties(&client, consul_host, app_prefix).await?;
+    let yml_properties = input::load_layers(input_files, None, input_opts)?;
+    Ok(DiffResult::new(
+        &yml_properties,
+        &consul_properties,
+        compare_opts,
+    ))
+}
+
+fn load_manifest(file_path: &PathBuf) -> Result<Manifest> {
+    progress!("Loading manifest from file: {}", file_path.display());
+
+    let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+    match file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("toml") => toml::from_str(&contents).map_err(|e| Error::read(file_path, e)),
+        _ => serde_yaml::from_str(&contents).map_err(|e| Error::read(file_path, e)),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    cli::{CopyArgs, GlobalOpts},
+    consul,
+    diff::{self, Change},
+    error::{Outcome, Result},
+    output::outln,
+    plan::{Operation, Plan},
+    properties::ConsulProperties,
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &CopyArgs) -> Result<Outcome> {
+    let source_host = args.source_host.as_ref().unwrap_or(&global.consul_host);
+    let dest_host = args.dest_host.as_ref().unwrap_or(source_host);
+
+    let source_client = (source_host != &global.consul_host)
+        .then(|| consul::client(source_host, &global.connection))
+        .transpose()?;
+    let source_client = source_client.as_ref().unwrap_or(client);
+    let dest_client = (dest_host != &global.consul_host)
+        .then(|| consul::client(dest_host, &global.connection))
+        .transpose()?;
+    let dest_client = dest_client.as_ref().unwrap_or(client);
+
+    let source_properties: Vec<_> =
+        consul::load_properties(source_client, source_host, &args.source_prefix)
+            .await?
+            .into_iter()
+            .map(|item| ConsulProperties {
+                key: rewrite_key(&item.key, &args.rewrite),
+                value: item.value,
+            })
+            .collect();
+    let dest_properties =
+        consul::load_properties(dest_client, dest_host, &args.dest_prefix).await?;
+
+    let operations =
+        diff::compare_properties(&source_properties, &dest_properties, &global.compare)
+            .into_iter()
+            .filter_map(|change| match change {
+                Change::OnlyInFile(item) => Some(Operation::Create {
+                    key: item.key,
+                    value: item.value,
+                }),
+                Change::Changed {
+                    key,
+                    file_value,
+                    consul_value,
+                } => Some(Operation::Update {
+                    key,
+                    old_value: consul_value,
+                    new_value: file_value,
+                }),
+                Change::OnlyInConsul(_) => None,
+            })
+            .collect();
+    let plan = Plan::new(&args.dest_prefix, operations);
+
+    if plan.is_empty() {
+        outln!("No differences found.");
+        return Ok(Outcome::Clean);
+    }
+
+    if args.dry_run {
+        plan.print();
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = if args.interactive {
+        plan.select_interactively()?
+    } else {
+        plan
+    };
+
+    plan.apply(dest_client).await?;
+    outln!(
+        "{} keys copied from {} to {}.",
+        plan.operations.len(),
+        args.source_prefix,
+        args.dest_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
+
+fn rewrite_key(key: &str, rules: &[(String, String)]) -> String {
+    rules
+        .iter()
+        .fold(key.to_string(), |key, (from, to)| key.replace(from, to))
+}
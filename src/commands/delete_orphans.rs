@@ -0,0 +1,57 @@
+use crate::{
+    cli::{DeleteOrphansArgs, GlobalOpts},
+    consul,
+    diff::{self, Change},
+    error::{Outcome, Result},
+    input,
+    output::outln,
+    plan::{Operation, Plan},
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &DeleteOrphansArgs,
+) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_properties(client, &global.consul_host, &args.app_prefix).await?;
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+    input::require_keys(&args.input_property, &yml_properties)?;
+
+    let operations = diff::compare_properties(&yml_properties, &consul_properties, &global.compare)
+        .into_iter()
+        .filter_map(|change| match change {
+            Change::OnlyInConsul(item) => Some(Operation::Delete {
+                key: item.key,
+                old_value: item.value,
+            }),
+            _ => None,
+        })
+        .collect();
+    let plan = Plan::new(&args.app_prefix, operations);
+
+    if plan.is_empty() {
+        outln!("No orphan keys found.");
+        return Ok(Outcome::Clean);
+    }
+
+    if args.dry_run {
+        plan.print();
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = if args.interactive {
+        plan.select_interactively()?
+    } else {
+        plan
+    };
+
+    plan.apply(client).await?;
+    outln!(
+        "{} keys deleted in {}.",
+        plan.operations.len(),
+        args.app_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
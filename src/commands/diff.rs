@@ -0,0 +1,366 @@
+use clap::ValueEnum;
+use futures_util::future;
+use std::cmp::Reverse;
+
+use crate::{
+    cli::{DiffArgs, FailOn, GlobalOpts, OutputFormat, Severity},
+    consul,
+    diff::DiffResult,
+    error::{Error, Outcome, Result},
+    input,
+    output::{out, outln, progress},
+    properties::{self, ConsulProperties},
+    report,
+    severity::{self, SeverityRule},
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &DiffArgs) -> Result<Outcome> {
+    if args.summary_only && args.output_format != OutputFormat::Text {
+        return Err(Error::Usage(
+            "--summary-only can only be used with the text output format.".to_string(),
+        ));
+    }
+    if args.group && args.output_format != OutputFormat::Text {
+        return Err(Error::Usage(
+            "--group can only be used with the text output format.".to_string(),
+        ));
+    }
+    if args.detect_renames && !matches!(args.output_format, OutputFormat::Text | OutputFormat::Json)
+    {
+        return Err(Error::Usage(
+            "--detect-renames can only be used with the text and json output formats.".to_string(),
+        ));
+    }
+    if args.full_state
+        && !matches!(
+            args.output_format,
+            OutputFormat::KvImport | OutputFormat::Terraform
+        )
+    {
+        return Err(Error::Usage(
+            "--full-state can only be used with the kv-import and terraform output formats."
+                .to_string(),
+        ));
+    }
+
+    let severity_rules = severity::load(args.severity_file.as_deref())?;
+
+    if let Some(compare_with) = &args.compare_with {
+        let consul_properties = input::load(compare_with, &global.input)?;
+        let target_properties = load_input(global, args)?.unwrap_or_default();
+        let result = DiffResult::new(&target_properties, &consul_properties, &global.compare);
+        let name = compare_with.display().to_string();
+        return report(global, args, &severity_rules, vec![(name, result)]);
+    }
+
+    let app_prefixes = consul::expand_prefixes(client, &args.app_prefix).await?;
+    if app_prefixes.is_empty() {
+        return Err(Error::Rejected("No app prefixes found.".to_string()));
+    }
+    if app_prefixes.len() > 1 && args.target_prefix.is_some() {
+        return Err(Error::Usage(
+            "--target-prefix can only be used with a single --app-prefix.".to_string(),
+        ));
+    }
+
+    let input_properties = load_input(global, args)?;
+    let target_host = args
+        .target_consul_host
+        .as_ref()
+        .unwrap_or(&global.consul_host);
+    let mut target_connection = global.connection.clone();
+    if let Some(target_datacenter) = &args.target_datacenter {
+        target_connection.datacenter = Some(target_datacenter.clone());
+    }
+    let target_client = (target_host != &global.consul_host || args.target_datacenter.is_some())
+        .then(|| consul::client(target_host, &target_connection))
+        .transpose()?;
+    let target_client = target_client.as_ref().unwrap_or(client);
+
+    // Every prefix is fetched concurrently over the same clients
+    let differences = future::try_join_all(app_prefixes.iter().map(|app_prefix| {
+        let input_properties = &input_properties;
+        async move {
+            let consul_properties =
+                load_consul(client, &global.consul_host, app_prefix, global, args).await?;
+            let target_properties = match input_properties {
+                Some(input_properties) => input_properties.clone(),
+                None => {
+                    let target_prefix = args.target_prefix.as_ref().unwrap_or(app_prefix);
+                    load_consul(target_client, target_host, target_prefix, global, args).await?
+                }
+            };
+            Ok::<_, Error>(DiffResult::new(
+                &target_properties,
+                &consul_properties,
+                &global.compare,
+            ))
+        }
+    }))
+    .await?;
+
+    report(
+        global,
+        args,
+        &severity_rules,
+        app_prefixes.into_iter().zip(differences).collect(),
+    )
+}
+
+/// Reads the keys under the app prefix, or with --blob-key the properties in
+/// that one key.
+async fn load_consul(
+    client: &consul::Client,
+    consul_host: &str,
+    app_prefix: &str,
+    global: &GlobalOpts,
+    args: &DiffArgs,
+) -> Result<Vec<ConsulProperties>> {
+    let Some(blob_key) = &args.blob_key else {
+        return consul::load_properties(client, consul_host, app_prefix).await;
+    };
+
+    progress!(
+        "Loading properties from consul host: {} to key {}/{}",
+        consul_host,
+        app_prefix,
+        blob_key
+    );
+    match consul::load_value(client, app_prefix, blob_key).await? {
+        Some(contents) => input::parse_value(
+            &format!("{}/{}", app_prefix, blob_key),
+            &contents,
+            &global.input,
+        ),
+        None => Err(Error::Rejected(format!(
+            "Key {}/{} not found.",
+            app_prefix, blob_key
+        ))),
+    }
+}
+
+/// Reads the input property file, or returns nothing when comparing against
+/// another cluster.
+fn load_input(global: &GlobalOpts, args: &DiffArgs) -> Result<Option<Vec<ConsulProperties>>> {
+    if args.input_property.is_empty()
+        && (args.target_consul_host.is_some()
+            || args.target_datacenter.is_some()
+            || args.target_prefix.is_some())
+    {
+        return Ok(None);
+    }
+    input::load_layers(&args.input_property, args.git_ref.as_deref(), &global.input).map(Some)
+}
+
+/// Reports the result of each app prefix, in its own section when there
+/// are several.
+fn report(
+    global: &GlobalOpts,
+    args: &DiffArgs,
+    severity_rules: &[SeverityRule],
+    mut results: Vec<(String, DiffResult)>,
+) -> Result<Outcome> {
+    for (_, result) in &mut results {
+        if args.detect_renames {
+            result.detect_renames();
+        }
+        result.sort(global.sort);
+    }
+    let drifted = results
+        .iter()
+        .filter(|(_, result)| !result.is_empty())
+        .count();
+    let failed = fails(args, severity_rules, &results);
+
+    if let Some(output_template) = &args.output_template {
+        let contents = report::render_template(output_template, &results)?;
+        report::write(args.output_file.as_deref(), args.output_format, &contents)?;
+        return Ok(Outcome::drift_if(failed));
+    }
+
+    if args.output_format != OutputFormat::Text {
+        // Files are never colored
+        let color = args.output_file.is_none() && global.color.enabled();
+        let locations = match args.output_format {
+            OutputFormat::Github => input::locate(&args.input_property, &global.input),
+            _ => input::Locations::new(),
+        };
+        let contents = report::render_all(
+            &results,
+            args.output_format,
+            color,
+            global.sort,
+            args.full_state,
+            &locations,
+        )?;
+        report::write(args.output_file.as_deref(), args.output_format, &contents)?;
+        return Ok(Outcome::drift_if(failed));
+    }
+
+    if let [(_, result)] = results.as_slice() {
+        if !args.summary_only {
+            print_differences(global, args, severity_rules, result)?;
+        }
+        out!("{}", report::summary(result));
+        if result.is_empty() {
+            return Ok(Outcome::Clean);
+        }
+
+        write_output(args, result)?;
+        return Ok(Outcome::drift_if(failed));
+    }
+
+    // The output file holds the differences of every prefix under their
+    // full keys
+    let compared = results.len();
+    let mut all_differences = DiffResult::default();
+    for (app_prefix, result) in results {
+        outln!();
+        outln!("== {} ==", app_prefix);
+        if !args.summary_only {
+            print_differences(global, args, severity_rules, &result)?;
+        }
+        out!("{}", report::summary(&result));
+        all_differences.extend(result.with_prefix(&app_prefix));
+    }
+
+    outln!();
+    outln!(
+        "{} prefixes compared, {} with differences.",
+        compared,
+        drifted
+    );
+    if drifted > 0 {
+        write_output(args, &all_differences)?;
+    }
+
+    Ok(Outcome::drift_if(failed))
+}
+
+/// Whether the differences of the kinds --fail-on counts, and of at least
+/// the --fail-on-severity, are more than --fail-threshold allows. A rename
+/// counts as adding and removing a key.
+fn fails(
+    args: &DiffArgs,
+    severity_rules: &[SeverityRule],
+    results: &[(String, DiffResult)],
+) -> bool {
+    let counts = |kinds: &[FailOn]| {
+        args.fail_on
+            .iter()
+            .any(|fail_on| *fail_on == FailOn::Any || kinds.contains(fail_on))
+    };
+    let severe = |key: &str| {
+        args.fail_on_severity
+            .is_none_or(|severity| severity::classify(severity_rules, key) >= severity)
+    };
+    let counted = results
+        .iter()
+        .flat_map(|(_, result)| {
+            let added: &[FailOn] = &[FailOn::Added];
+            let removed: &[FailOn] = &[FailOn::Removed];
+            let changed: &[FailOn] = &[FailOn::Changed];
+            let renamed: &[FailOn] = &[FailOn::Added, FailOn::Removed];
+            result
+                .only_in_file
+                .iter()
+                .map(move |item| (added, item.key.as_str()))
+                .chain(
+                    result
+                        .only_in_consul
+                        .iter()
+                        .map(move |item| (removed, item.key.as_str())),
+                )
+                .chain(
+                    result
+                        .changed
+                        .iter()
+                        .map(move |change| (changed, change.0.as_str())),
+                )
+                .chain(
+                    result
+                        .renamed
+                        .iter()
+                        .map(move |rename| (renamed, rename.0.as_str())),
+                )
+        })
+        .filter(|(kinds, key)| counts(kinds) && severe(key))
+        .count();
+    counted > args.fail_threshold
+}
+
+/// Prints the differences of a result, under a heading per namespace with
+/// --group or per severity with --severity-file.
+fn print_differences(
+    global: &GlobalOpts,
+    args: &DiffArgs,
+    severity_rules: &[SeverityRule],
+    result: &DiffResult,
+) -> Result<()> {
+    if result.is_empty() {
+        outln!("No differences found.");
+        if !result.has_remarks() {
+            return Ok(());
+        }
+    }
+
+    let render = |result: &DiffResult| {
+        report::render(
+            result,
+            OutputFormat::Text,
+            global.color.enabled(),
+            global.sort,
+        )
+    };
+    let groups: Vec<(String, DiffResult)> = if args.severity_file.is_some() {
+        // The most severe differences come first
+        result
+            .group_by(|key| Reverse(severity::classify(severity_rules, key)))
+            .into_iter()
+            .map(|(Reverse(severity), group)| (severity_name(severity), group))
+            .collect()
+    } else if args.group {
+        result
+            .group_by_namespace()
+            .into_iter()
+            .map(|(namespace, group)| match namespace.is_empty() {
+                true => ("(top level)".to_string(), group),
+                false => (namespace, group),
+            })
+            .collect()
+    } else {
+        out!("{}", render(result)?);
+        return Ok(());
+    };
+
+    for (name, group) in groups {
+        if group.is_empty() && !group.has_remarks() {
+            continue;
+        }
+        outln!(
+            "[{}] {} added, {} removed, {} changed",
+            name,
+            group.only_in_file.len(),
+            group.only_in_consul.len(),
+            group.changed.len()
+        );
+        out!("{}", render(&group)?);
+    }
+    Ok(())
+}
+
+fn severity_name(severity: Severity) -> String {
+    severity
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+fn write_output(args: &DiffArgs, result: &DiffResult) -> Result<()> {
+    match &args.output_file {
+        Some(output_file) => {
+            properties::write_properties_file(output_file, &result.consul_properties())?
+        }
+        None => outln!("No output file provided."),
+    }
+    Ok(())
+}
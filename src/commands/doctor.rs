@@ -0,0 +1,85 @@
+use crate::{
+    cli::{DoctorArgs, GlobalOpts},
+    consul::{self, TxnOperation},
+    error::{Error, Outcome, Result},
+    output::outln,
+};
+
+/// Key written and deleted again in one transaction by `--check-write`.
+const PROBE_KEY: &str = ".consul-help-doctor";
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &DoctorArgs,
+) -> Result<Outcome> {
+    outln!(
+        "Checking consul host: {} and key {}",
+        global.consul_host,
+        args.app_prefix
+    );
+
+    let mut failed = 0;
+
+    // Without an agent none of the other checks can succeed
+    match consul::agent_config(client).await {
+        Ok(config) => report_ok(&format!(
+            "agent {} reachable (consul {}, datacenter {})",
+            config.node_name, config.version, config.datacenter
+        )),
+        Err(error) => {
+            report_fail(&error.to_string());
+            return Err(Error::Rejected("1 check failed.".to_string()));
+        }
+    }
+
+    match consul::leader(client).await {
+        Ok(leader) if !leader.is_empty() => report_ok(&format!("cluster leader is {}", leader)),
+        Ok(_) => fail(&mut failed, "cluster has no leader"),
+        Err(error) => fail(&mut failed, &error.to_string()),
+    }
+
+    match consul::load_pairs(client, &args.app_prefix).await {
+        Ok(pairs) if pairs.is_empty() => fail(
+            &mut failed,
+            &format!("no keys found under {}", args.app_prefix),
+        ),
+        Ok(pairs) => report_ok(&format!(
+            "{} keys readable under {}",
+            pairs.len(),
+            args.app_prefix
+        )),
+        Err(error) => fail(&mut failed, &error.to_string()),
+    }
+
+    if args.check_write {
+        let probe_key = format!("{}/{}", args.app_prefix, PROBE_KEY);
+        let operations = [
+            TxnOperation::set(probe_key.clone(), ""),
+            TxnOperation::delete(probe_key),
+        ];
+        match consul::transaction(client, &operations).await {
+            Ok(()) => report_ok(&format!("write permitted under {}", args.app_prefix)),
+            Err(error) => fail(&mut failed, &error.to_string()),
+        }
+    }
+
+    match failed {
+        0 => Ok(Outcome::Clean),
+        1 => Err(Error::Rejected("1 check failed.".to_string())),
+        _ => Err(Error::Rejected(format!("{} checks failed.", failed))),
+    }
+}
+
+fn report_ok(message: &str) {
+    outln!("[ok]   {}", message);
+}
+
+fn report_fail(message: &str) {
+    outln!("[fail] {}", message);
+}
+
+fn fail(failed: &mut usize, message: &str) {
+    report_fail(message);
+    *failed += 1;
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::{
+    cli::{DuplicatesArgs, GlobalOpts},
+    consul,
+    error::{Outcome, Result},
+    input,
+    output::outln,
+    properties::ConsulProperties,
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &DuplicatesArgs,
+) -> Result<Outcome> {
+    let mut sources = Vec::new();
+    if let Some(app_prefix) = &args.app_prefix {
+        let consul_properties =
+            consul::load_properties(client, &global.consul_host, app_prefix).await?;
+        sources.push((format!("consul {}", app_prefix), consul_properties));
+    }
+    if !args.input_property.is_empty() {
+        let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+        sources.push(("file".to_string(), yml_properties));
+    }
+
+    let mut found = 0;
+    for (source, properties) in &sources {
+        for (value, keys) in duplicates(properties, args.min_length) {
+            let value = match args.show_values {
+                true => format!("{:?}", value),
+                false => masked(value),
+            };
+            outln!("Same value in {} keys in {}: {}", keys.len(), source, value);
+            for key in keys {
+                outln!("  {}", key);
+            }
+            found += 1;
+        }
+    }
+
+    if found == 0 {
+        outln!("No duplicate values found.");
+        return Ok(Outcome::Clean);
+    }
+    outln!("{} values held by several keys.", found);
+    Ok(Outcome::Drift)
+}
+
+/// The values held by more than one key, long enough to be worth
+/// consolidating, with their keys in order. Empty values, such as those of
+/// folder keys, are always left out.
+fn duplicates(properties: &[ConsulProperties], min_length: usize) -> Vec<(&str, Vec<&str>)> {
+    let mut keys_by_value: HashMap<&str, Vec<&str>> = HashMap::new();
+    for item in properties {
+        if item.value.chars().count() >= min_length.max(1) {
+            keys_by_value
+                .entry(&item.value)
+                .or_default()
+                .push(&item.key);
+        }
+    }
+
+    let mut duplicates: Vec<_> = keys_by_value
+        .into_iter()
+        .filter(|(_, keys)| keys.len() > 1)
+        .map(|(value, mut keys)| {
+            keys.sort();
+            (value, keys)
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.1.cmp(&b.1));
+    duplicates
+}
+
+/// Stands in for a value that may be a secret, giving only its length.
+fn masked(value: &str) -> String {
+    format!("<{} characters, hidden>", value.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn groups_the_keys_sharing_a_long_enough_value() {
+        let properties = [
+            property("b/password", "hunter2hunter2"),
+            property("a/password", "hunter2hunter2"),
+            property("a/enabled", "true"),
+            property("b/enabled", "true"),
+            property("a/", ""),
+            property("b/", ""),
+        ];
+        assert_eq!(
+            duplicates(&properties, 8),
+            [("hunter2hunter2", vec!["a/password", "b/password"])]
+        );
+        assert_eq!(duplicates(&properties, 0).len(), 2);
+    }
+
+    #[test]
+    fn masks_values_by_their_length() {
+        assert_eq!(masked("hunter2hunter2"), "<14 characters, hidden>");
+    }
+}
@@ -0,0 +1,88 @@
+use regex::Regex;
+use std::{fs, path::Path};
+
+use crate::{
+    cli::{ExportArgs, ExportFormat, GlobalOpts},
+    consul,
+    error::{Error, Outcome, Result},
+    export::{self, ansible, env_file, kubernetes, Metadata},
+    input,
+    output::{out, outln},
+    properties::{self, ConsulProperties},
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &ExportArgs,
+) -> Result<Outcome> {
+    let mut properties = match &args.app_prefix {
+        Some(app_prefix) => {
+            consul::load_properties(client, &global.consul_host, app_prefix).await?
+        }
+        None => input::load_layers(&args.input_property, None, &global.input)?,
+    };
+
+    let contents = match args.output_format {
+        ExportFormat::Properties => {
+            if let Some(output_file) = &args.output_file {
+                properties::write_properties_file(output_file, &properties)?;
+                return written(&properties, output_file);
+            }
+            properties
+                .iter()
+                .map(|item| format!("{}={}\n", item.key, item.value))
+                .collect()
+        }
+        ExportFormat::ConfigMap => kubernetes::config_map(&properties, &metadata(args))?,
+        ExportFormat::Ansible => ansible::render(&properties, &args.var_prefix)?,
+        ExportFormat::EnvFile => env_file::render(&properties, &global.input.env_separator)?,
+        ExportFormat::Secret => {
+            let secret_pattern = Regex::new(&args.secret_pattern)
+                .map_err(|e| Error::Usage(format!("Invalid --secret-pattern: {}", e)))?;
+            properties.retain(|item| secret_pattern.is_match(&item.key));
+            kubernetes::secret(&properties, &metadata(args), args.string_data)?
+        }
+    };
+
+    match &args.output_file {
+        Some(output_file) => {
+            fs::write(output_file, contents).map_err(|e| Error::write(output_file, e))?;
+            written(&properties, output_file)
+        }
+        None => {
+            out!("{}", contents);
+            Ok(Outcome::Clean)
+        }
+    }
+}
+
+fn written(properties: &[ConsulProperties], output_file: &Path) -> Result<Outcome> {
+    outln!(
+        "{} keys written to {}.",
+        properties.len(),
+        output_file.display()
+    );
+    Ok(Outcome::Clean)
+}
+
+/// The metadata of the exported manifest, named after the app prefix or the
+/// first input file unless --name is given.
+fn metadata(args: &ExportArgs) -> Metadata<'_> {
+    let name = match (&args.name, &args.app_prefix, args.input_property.first()) {
+        (Some(name), _, _) => name.clone(),
+        (None, Some(app_prefix), _) => export::object_name(app_prefix),
+        (None, None, Some(input_property)) => export::object_name(
+            &input_property
+                .file_stem()
+                .map(|stem| stem.to_string_lossy())
+                .unwrap_or_default(),
+        ),
+        (None, None, None) => String::new(),
+    };
+    Metadata {
+        name,
+        namespace: args.namespace.as_deref(),
+        labels: &args.label,
+    }
+}
@@ -0,0 +1,19 @@
+use crate::{
+    cli::GetArgs,
+    consul,
+    error::{Error, Outcome, Result},
+    output::outln,
+};
+
+pub async fn run(client: &consul::Client, args: &GetArgs) -> Result<Outcome> {
+    match consul::load_value(client, &args.app_prefix, &args.key).await? {
+        Some(value) => {
+            outln!("{}", value);
+            Ok(Outcome::Clean)
+        }
+        None => Err(Error::Rejected(format!(
+            "Key {}/{} not found.",
+            args.app_prefix, args.key
+        ))),
+    }
+}
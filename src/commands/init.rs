@@ -0,0 +1,63 @@
+use serde_yaml::{Mapping, Value};
+use std::fs;
+
+use crate::{
+    cli::{GlobalOpts, InitArgs},
+    consul,
+    error::{Error, Outcome, Result},
+    output::outln,
+    properties,
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &InitArgs) -> Result<Outcome> {
+    if args.output_file.exists() && !args.force {
+        return Err(Error::Usage(format!(
+            "{} already exists, use --force to overwrite it.",
+            args.output_file.display()
+        )));
+    }
+
+    let consul_properties =
+        consul::load_properties(client, &global.consul_host, &args.app_prefix).await?;
+    let yaml = properties::sort_yaml(properties::unflatten_yaml(&consul_properties));
+
+    let mut contents = format!(
+        "# Application configuration generated by consul-help.\n\
+         # Source: consul host {} under key {}\n\
+         # Keys: {}\n",
+        global.consul_host,
+        args.app_prefix,
+        consul_properties.len()
+    );
+
+    // Each top-level section is serialized on its own so it can carry a comment
+    if let Value::Mapping(mapping) = yaml {
+        for (key, value) in mapping {
+            let section = properties::value_to_string(&key);
+            let leaves = consul_properties
+                .iter()
+                .filter(|item| {
+                    item.key == section
+                        || item.key.starts_with(&format!("{}/", section))
+                        || item.key.starts_with(&format!("{}[", section))
+                })
+                .count();
+
+            let mut single = Mapping::new();
+            single.insert(key, value);
+            contents.push_str(&format!(
+                "\n# {}/{} ({} keys)\n",
+                args.app_prefix, section, leaves
+            ));
+            contents.push_str(&serde_yaml::to_string(&single).map_err(Error::serialize)?);
+        }
+    }
+
+    fs::write(&args.output_file, contents).map_err(|e| Error::write(&args.output_file, e))?;
+    outln!(
+        "{} keys written to {}.",
+        consul_properties.len(),
+        args.output_file.display()
+    );
+    Ok(Outcome::Clean)
+}
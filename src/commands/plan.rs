@@ -0,0 +1,77 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    cli::{GlobalOpts, PlanArgs},
+    consul,
+    diff::{self, Change},
+    error::{Outcome, Result},
+    input,
+    output::{outln, progress},
+    plan::{Operation, Plan, PlanFile},
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &PlanArgs) -> Result<Outcome> {
+    progress!(
+        "Loading properties from consul host: {} to key {}",
+        global.consul_host,
+        args.app_prefix
+    );
+
+    // The values and indexes come from the same read, so apply can tell
+    // whether anything changed after the plan was made
+    let pairs = consul::load_pairs(client, &args.app_prefix).await?;
+    let indexes = consul::modify_indexes(&args.app_prefix, &pairs);
+    let consul_properties = consul::to_properties(&args.app_prefix, pairs)?;
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+    if args.delete {
+        input::require_keys(&args.input_property, &yml_properties)?;
+    }
+
+    let operations = diff::compare_properties(&yml_properties, &consul_properties, &global.compare)
+        .into_iter()
+        .filter_map(|change| match change {
+            Change::OnlyInFile(item) => Some(Operation::Create {
+                key: item.key,
+                value: item.value,
+            }),
+            Change::Changed {
+                key,
+                file_value,
+                consul_value,
+            } => Some(Operation::Update {
+                key,
+                old_value: consul_value,
+                new_value: file_value,
+            }),
+            Change::OnlyInConsul(item) if args.delete => Some(Operation::Delete {
+                key: item.key,
+                old_value: item.value,
+            }),
+            Change::OnlyInConsul(_) => None,
+        })
+        .collect();
+    let plan = Plan::new(&args.app_prefix, operations);
+    let drift = !plan.is_empty();
+
+    if drift {
+        plan.print();
+    } else {
+        outln!("No differences found.");
+    }
+
+    // A clock set before the Unix epoch records 0 rather than failing
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    PlanFile::new(
+        &global.consul_host,
+        &global.connection,
+        created_at,
+        plan,
+        &indexes,
+    )
+    .save(&args.output_file)?;
+    outln!("Plan written to {}.", args.output_file.display());
+
+    Ok(Outcome::drift_if(drift))
+}
@@ -0,0 +1,32 @@
+use std::{fs::File, io::Write};
+
+use crate::{
+    cli::{GlobalOpts, PullArgs},
+    consul,
+    error::{Error, Outcome, Result},
+    output::{out, outln},
+    properties,
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &PullArgs) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_properties(client, &global.consul_host, &args.app_prefix).await?;
+    let yaml = properties::unflatten_yaml(&consul_properties);
+    let contents = serde_yaml::to_string(&yaml).map_err(Error::serialize)?;
+
+    match &args.output_file {
+        Some(output_file) => {
+            let mut file = File::create(output_file).map_err(|e| Error::write(output_file, e))?;
+            file.write_all(contents.as_bytes())
+                .map_err(|e| Error::write(output_file, e))?;
+            outln!(
+                "{} keys written to {}.",
+                consul_properties.len(),
+                output_file.display()
+            );
+        }
+        None => out!("{}", contents),
+    }
+
+    Ok(Outcome::Clean)
+}
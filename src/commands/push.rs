@@ -0,0 +1,61 @@
+use crate::{
+    cli::{GlobalOpts, PushArgs},
+    consul,
+    diff::{self, Change},
+    error::{Outcome, Result},
+    input,
+    output::outln,
+    plan::{Operation, Plan},
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &PushArgs) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_properties(client, &global.consul_host, &args.app_prefix).await?;
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+
+    let operations = diff::compare_properties(&yml_properties, &consul_properties, &global.compare)
+        .into_iter()
+        .filter_map(|change| match change {
+            Change::OnlyInFile(item) => Some(Operation::Create {
+                key: item.key,
+                value: item.value,
+            }),
+            Change::Changed {
+                key,
+                file_value,
+                consul_value,
+            } => Some(Operation::Update {
+                key,
+                old_value: consul_value,
+                new_value: file_value,
+            }),
+            Change::OnlyInConsul(_) => None,
+        })
+        .collect();
+    let plan = Plan::new(&args.app_prefix, operations);
+
+    if plan.is_empty() {
+        outln!("No differences found.");
+        return Ok(Outcome::Clean);
+    }
+
+    if args.dry_run {
+        plan.print();
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = if args.interactive {
+        plan.select_interactively()?
+    } else {
+        plan
+    };
+
+    plan.apply(client).await?;
+    outln!(
+        "{} keys written to {}.",
+        plan.operations.len(),
+        args.app_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use crate::{
+    cli::{GlobalOpts, RenamePrefixArgs},
+    consul::{self, TxnOperation, MAX_TXN_OPERATIONS},
+    error::{Error, Outcome, Result},
+    output::{outln, progress},
+};
+
+/// A key under the old prefix, relative to it, with what the move carries
+/// over and the modify index its deletion checks.
+struct MovedKey {
+    key: String,
+    value: String,
+    flags: u64,
+    modify_index: u64,
+}
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &RenamePrefixArgs,
+) -> Result<Outcome> {
+    progress!(
+        "Loading keys from consul host: {} to key {}",
+        global.consul_host,
+        args.from
+    );
+    let keys = moved_keys(client, &args.from).await?;
+
+    if keys.is_empty() {
+        outln!("No keys found under {}.", args.from);
+        return Ok(Outcome::Clean);
+    }
+
+    // Each key is copied and deleted in the same transaction, so a failed
+    // batch never leaves a key missing from both prefixes.
+    let operations_per_key = if args.delete_source { 2 } else { 1 };
+    if keys.len() * operations_per_key > MAX_TXN_OPERATIONS && !args.allow_split {
+        return Err(Error::Usage(format!(
+            "Moving {} keys takes {} operations, more than the {} of a single transaction. Pass --allow-split to move them in several, which a failure can leave half done.",
+            keys.len(),
+            keys.len() * operations_per_key,
+            MAX_TXN_OPERATIONS
+        )));
+    }
+
+    let target = format!("{}/", args.to);
+    let existing: HashSet<_> = consul::load_pairs(client, &args.to)
+        .await?
+        .into_iter()
+        .filter_map(|pair| Some(pair.key.strip_prefix(&target)?.to_string()))
+        .collect();
+    let overwritten: Vec<_> = keys
+        .iter()
+        .filter(|item| existing.contains(&item.key))
+        .map(|item| format!("{}{}", target, item.key))
+        .collect();
+    if !overwritten.is_empty() {
+        return Err(Error::Rejected(format!(
+            "{} keys already exist under {}, which the move would overwrite: {}",
+            overwritten.len(),
+            args.to,
+            overwritten.join(", ")
+        )));
+    }
+
+    if args.dry_run {
+        for item in &keys {
+            outln!("+ {}/{} = {:?}", args.to, item.key, item.value);
+            if args.delete_source {
+                outln!("- {}/{} = {:?}", args.from, item.key, item.value);
+            }
+        }
+        return Ok(Outcome::Drift);
+    }
+
+    // The keys are created with an index of 0 and deleted at the index they
+    // were read at, so a key written meanwhile rolls the transaction back
+    for batch in keys.chunks(MAX_TXN_OPERATIONS / operations_per_key) {
+        let mut operations = Vec::new();
+        for item in batch {
+            operations.push(
+                TxnOperation::cas(format!("{}/{}", args.to, item.key), &item.value, 0)
+                    .with_flags(item.flags),
+            );
+            if args.delete_source {
+                operations.push(TxnOperation::delete_cas(
+                    format!("{}/{}", args.from, item.key),
+                    item.modify_index,
+                ));
+            }
+        }
+        consul::transaction(client, &operations).await?;
+    }
+
+    outln!(
+        "{} keys {} from {} to {}.",
+        keys.len(),
+        if args.delete_source {
+            "moved"
+        } else {
+            "copied"
+        },
+        args.from,
+        args.to
+    );
+    Ok(Outcome::Clean)
+}
+
+/// The keys under a prefix with their flags and modify indexes. Folder keys
+/// hold no value and are left where they are.
+async fn moved_keys(client: &consul::Client, prefix: &str) -> Result<Vec<MovedKey>> {
+    let source = format!("{}/", prefix);
+    let mut keys = Vec::new();
+    for pair in consul::load_pairs(client, prefix).await? {
+        let Some(key) = pair.key.strip_prefix(&source) else {
+            continue;
+        };
+        if key.is_empty() || key.ends_with('/') {
+            continue;
+        }
+        let key = key.to_string();
+        let (flags, modify_index) = (pair.flags, pair.modify_index);
+        let (_, value) = consul::decode_value(pair)?;
+        keys.push(MovedKey {
+            key,
+            value,
+            flags,
+            modify_index,
+        });
+    }
+    Ok(keys)
+}
@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+
+use crate::{
+    cli::RestoreArgs,
+    consul::{self, TxnOperation, MAX_TXN_OPERATIONS},
+    error::{Outcome, Result},
+    output::outln,
+    snapshot::Snapshot,
+};
+
+pub async fn run(client: &consul::Client, args: &RestoreArgs) -> Result<Outcome> {
+    let snapshot = Snapshot::load(&args.snapshot)?;
+    let target_prefix = args.target_prefix.as_ref().unwrap_or(&snapshot.app_prefix);
+
+    let mut operations: Vec<_> = snapshot
+        .entries
+        .iter()
+        .map(|entry| {
+            TxnOperation::set(format!("{}/{}", target_prefix, entry.key), &entry.value)
+                .with_flags(entry.flags)
+        })
+        .collect();
+
+    let mut pruned = 0;
+    if args.prune {
+        let snapshot_keys: HashSet<_> = snapshot.entries.iter().map(|entry| &entry.key).collect();
+        let prefix = format!("{}/", target_prefix);
+        for pair in consul::load_pairs(client, target_prefix).await? {
+            let Some(key) = pair.key.strip_prefix(&prefix) else {
+                continue;
+            };
+            if !snapshot_keys.contains(&key.to_string()) {
+                operations.push(TxnOperation::delete(pair.key.clone()));
+                pruned += 1;
+            }
+        }
+    }
+
+    for batch in operations.chunks(MAX_TXN_OPERATIONS) {
+        consul::transaction(client, batch).await?;
+    }
+
+    outln!(
+        "{} keys restored and {} keys pruned in {}.",
+        snapshot.entries.len(),
+        pruned,
+        target_prefix
+    );
+    Ok(Outcome::Clean)
+}
@@ -0,0 +1,15 @@
+use crate::{
+    cli::SetArgs,
+    consul,
+    error::{Outcome, Result},
+    properties::ConsulProperties,
+};
+
+pub async fn run(client: &consul::Client, args: &SetArgs) -> Result<Outcome> {
+    let property = ConsulProperties {
+        key: args.key.clone(),
+        value: args.value.clone(),
+    };
+    consul::write_properties(client, &args.app_prefix, &[property]).await?;
+    Ok(Outcome::Clean)
+}
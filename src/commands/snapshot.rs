@@ -0,0 +1,65 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    cli::{GlobalOpts, SnapshotArgs},
+    consul,
+    error::{Outcome, Result},
+    output::{outln, progress},
+    snapshot::{Snapshot, SnapshotEntry},
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &SnapshotArgs,
+) -> Result<Outcome> {
+    progress!(
+        "Loading properties from consul host: {} to key {}",
+        global.consul_host,
+        args.app_prefix
+    );
+
+    let prefix = format!("{}/", args.app_prefix);
+    let mut entries = Vec::new();
+    for pair in consul::load_pairs(client, &args.app_prefix).await? {
+        let (flags, modify_index) = (pair.flags, pair.modify_index);
+        let (key, value) = consul::decode_value(pair)?;
+        if let Some(key) = key.strip_prefix(&prefix) {
+            entries.push(SnapshotEntry {
+                key: key.to_string(),
+                value,
+                flags,
+                modify_index,
+            });
+        }
+    }
+
+    // A clock set before the Unix epoch records 0 rather than failing
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let snapshot = Snapshot {
+        consul_host: global.consul_host.clone(),
+        app_prefix: args.app_prefix.clone(),
+        created_at,
+        entries,
+    };
+
+    let output_file = args.output_file.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "{}-{}.snapshot.json",
+            args.app_prefix.replace('/', "_"),
+            created_at
+        ))
+    });
+    snapshot.save(&output_file)?;
+    outln!(
+        "{} keys written to {}.",
+        snapshot.entries.len(),
+        output_file.display()
+    );
+    Ok(Outcome::Clean)
+}
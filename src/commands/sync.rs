@@ -0,0 +1,91 @@
+use crate::{
+    cli::{GlobalOpts, SyncArgs, SyncStrategy},
+    consul,
+    diff::{self, Change},
+    error::{Outcome, Result},
+    input,
+    output::outln,
+    plan::{Operation, Plan},
+};
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &SyncArgs) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_properties(client, &global.consul_host, &args.app_prefix).await?;
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+    if args.delete {
+        input::require_keys(&args.input_property, &yml_properties)?;
+    }
+    let changes = diff::compare_properties(&yml_properties, &consul_properties, &global.compare);
+
+    if changes.is_empty() {
+        outln!("No differences found.");
+        return Ok(Outcome::Clean);
+    }
+
+    let mut operations = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for change in changes {
+        match change {
+            Change::OnlyInFile(item) => operations.push(Operation::Create {
+                key: item.key,
+                value: item.value,
+            }),
+            Change::OnlyInConsul(item) if args.delete => operations.push(Operation::Delete {
+                key: item.key,
+                old_value: item.value,
+            }),
+            Change::OnlyInConsul(item) => outln!("Keeping {} only in consul", item.key),
+            Change::Changed {
+                key,
+                file_value,
+                consul_value,
+            } => match args.strategy {
+                SyncStrategy::PreferFile => operations.push(Operation::Update {
+                    key,
+                    old_value: consul_value,
+                    new_value: file_value,
+                }),
+                SyncStrategy::PreferConsul => outln!("Keeping consul value for {}", key),
+                SyncStrategy::Fail => conflicts.push((key, file_value, consul_value)),
+            },
+        }
+    }
+
+    if !conflicts.is_empty() {
+        eprintln!("Conflicting values found, nothing was written:");
+        for (key, file_value, consul_value) in conflicts {
+            eprintln!("{}: file={} consul={}", key, file_value, consul_value);
+        }
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = Plan::new(&args.app_prefix, operations);
+
+    if args.dry_run {
+        plan.print();
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = if args.interactive {
+        plan.select_interactively()?
+    } else {
+        plan
+    };
+
+    plan.apply(client).await?;
+
+    let deleted = plan
+        .operations
+        .iter()
+        .filter(|operation| matches!(operation, Operation::Delete { .. }))
+        .count();
+    outln!(
+        "{} keys written and {} keys deleted in {}.",
+        plan.operations.len() - deleted,
+        deleted,
+        args.app_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::{GlobalOpts, TreeArgs},
+    consul,
+    error::{Outcome, Result},
+    output::outln,
+};
+
+#[derive(Default)]
+struct Node {
+    value: Option<String>,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn leaf_count(&self) -> usize {
+        let own = usize::from(self.value.is_some());
+        own + self.children.values().map(Node::leaf_count).sum::<usize>()
+    }
+}
+
+pub async fn run(client: &consul::Client, global: &GlobalOpts, args: &TreeArgs) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_entries(client, &global.consul_host, &args.app_prefix).await?;
+
+    let mut root = Node::default();
+    for item in consul_properties {
+        let mut node = &mut root;
+        for segment in item.key.split('/').filter(|segment| !segment.is_empty()) {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        if !item.key.ends_with('/') {
+            node.value = Some(item.value);
+        }
+    }
+
+    outln!("{}", args.app_prefix);
+    print_children(&root, "", 1, args);
+    Ok(Outcome::Clean)
+}
+
+fn print_children(node: &Node, indent: &str, level: usize, args: &TreeArgs) {
+    let count = node.children.len();
+    for (position, (name, child)) in node.children.iter().enumerate() {
+        let last = position + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+
+        let collapsed =
+            !child.children.is_empty() && args.depth.is_some_and(|depth| level >= depth);
+        let label = match (&child.value, collapsed) {
+            (_, true) => format!("{}/ ({} keys)", name, child.leaf_count()),
+            (Some(value), false) => format!("{} = {}", name, truncate(value, args.max_value_width)),
+            (None, false) => format!("{}/", name),
+        };
+        outln!("{}{}{}", indent, branch, label);
+
+        if !collapsed {
+            let child_indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+            print_children(child, &child_indent, level + 1, args);
+        }
+    }
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    let value = value.replace('\n', "\\n");
+    if value.chars().count() <= width {
+        return value;
+    }
+    let truncated: String = value.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", truncated)
+}
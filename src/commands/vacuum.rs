@@ -0,0 +1,115 @@
+use crate::{
+    cli::{GlobalOpts, VacuumArgs},
+    consul,
+    error::{Outcome, Result},
+    output::outln,
+    plan::{Operation, Plan},
+    properties::ConsulProperties,
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &VacuumArgs,
+) -> Result<Outcome> {
+    let consul_properties =
+        consul::load_entries(client, &global.consul_host, &args.app_prefix).await?;
+
+    let operations = empty_folders(consul_properties)
+        .into_iter()
+        .map(|item| Operation::Delete {
+            key: item.key,
+            old_value: item.value,
+        })
+        .collect();
+    let plan = Plan::new(&args.app_prefix, operations);
+
+    if plan.is_empty() {
+        outln!("No empty folder keys found.");
+        return Ok(Outcome::Clean);
+    }
+
+    if !args.delete {
+        plan.print();
+        return Ok(Outcome::Drift);
+    }
+
+    let plan = if args.interactive {
+        plan.select_interactively()?
+    } else {
+        plan
+    };
+
+    plan.apply(client).await?;
+    outln!(
+        "{} empty folder keys deleted in {}.",
+        plan.operations.len(),
+        args.app_prefix
+    );
+
+    Ok(Outcome::Clean)
+}
+
+/// Folder keys end in a slash, and the app prefix's own folder key is left
+/// with an empty key once the prefix is stripped. Only the ones without a
+/// key holding something below them are empty, the rest still group keys
+fn empty_folders(entries: Vec<ConsulProperties>) -> Vec<ConsulProperties> {
+    let is_empty_folder = |item: &ConsulProperties| {
+        (item.key.is_empty() || item.key.ends_with('/')) && item.value.is_empty()
+    };
+    let (folders, kept): (Vec<_>, Vec<_>) = entries.into_iter().partition(is_empty_folder);
+
+    folders
+        .into_iter()
+        .filter(|folder| !kept.iter().any(|item| item.key.starts_with(&folder.key)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn keys(entries: Vec<ConsulProperties>) -> Vec<String> {
+        empty_folders(entries)
+            .into_iter()
+            .map(|item| item.key)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_folders_with_keys_below_them() {
+        let entries = vec![
+            property("", ""),
+            property("db/", ""),
+            property("db/url", "jdbc:h2:mem"),
+            property("cache/", ""),
+        ];
+
+        assert_eq!(keys(entries), vec!["cache/"]);
+    }
+
+    #[test]
+    fn deletes_the_folders_holding_only_empty_folders() {
+        let entries = vec![
+            property("", ""),
+            property("old/", ""),
+            property("old/nested/", ""),
+        ];
+
+        assert_eq!(keys(entries), vec!["", "old/", "old/nested/"]);
+    }
+
+    #[test]
+    fn keeps_folder_keys_with_a_value() {
+        let entries = vec![property("", ""), property("notes/", "kept")];
+
+        assert_eq!(keys(entries), Vec::<String>::new());
+    }
+}
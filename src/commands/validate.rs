@@ -0,0 +1,115 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    cli::{GlobalOpts, ValidateArgs},
+    error::{Error, Outcome, Result},
+    input,
+    output::{outln, progress},
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Rules {
+    /// Keys that must be present
+    required: Vec<String>,
+    /// Keys that must not be present
+    forbidden: Vec<String>,
+    /// Regular expressions the value of a key must match
+    patterns: HashMap<String, String>,
+    /// Type the value of a key must parse as
+    types: HashMap<String, ValueType>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ValueType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl ValueType {
+    fn name(self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Integer => "integer",
+            ValueType::Float => "float",
+            ValueType::Boolean => "boolean",
+        }
+    }
+
+    fn matches(self, value: &str) -> bool {
+        match self {
+            ValueType::String => true,
+            ValueType::Integer => value.parse::<i64>().is_ok(),
+            ValueType::Float => value.parse::<f64>().is_ok(),
+            ValueType::Boolean => value == "true" || value == "false",
+        }
+    }
+}
+
+pub fn run(global: &GlobalOpts, args: &ValidateArgs) -> Result<Outcome> {
+    let rules = load_rules(&args.rules)?;
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+    let values: HashMap<_, _> = yml_properties
+        .iter()
+        .map(|item| (item.key.as_str(), item.value.as_str()))
+        .collect();
+
+    let mut violations = Vec::new();
+
+    for key in &rules.required {
+        if !values.contains_key(key.as_str()) {
+            violations.push(format!("{}: required key is missing", key));
+        }
+    }
+    for key in &rules.forbidden {
+        if values.contains_key(key.as_str()) {
+            violations.push(format!("{}: forbidden key is present", key));
+        }
+    }
+    for (key, pattern) in &rules.patterns {
+        let regex = Regex::new(pattern).map_err(|e| Error::read(&args.rules, e))?;
+        if let Some(value) = values.get(key.as_str()) {
+            if !regex.is_match(value) {
+                violations.push(format!(
+                    "{}: value {:?} does not match {}",
+                    key, value, pattern
+                ));
+            }
+        }
+    }
+    for (key, value_type) in &rules.types {
+        if let Some(value) = values.get(key.as_str()) {
+            if !value_type.matches(value) {
+                violations.push(format!(
+                    "{}: value {:?} is not a {}",
+                    key,
+                    value,
+                    value_type.name()
+                ));
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        outln!("No violations found.");
+        return Ok(Outcome::Clean);
+    }
+
+    violations.sort();
+    violations
+        .iter()
+        .for_each(|violation| eprintln!("{}", violation));
+    Ok(Outcome::Drift)
+}
+
+fn load_rules(file_path: &PathBuf) -> Result<Rules> {
+    progress!("Loading rules from file: {}", file_path.display());
+
+    let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+    serde_yaml::from_str(&contents).map_err(|e| Error::read(file_path, e))
+}
@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+
+use crate::{
+    cli::{GlobalOpts, WatchArgs},
+    consul,
+    diff::DiffResult,
+    error::{Outcome, Result},
+    input,
+    output::{outln, progress},
+};
+
+pub async fn run(
+    client: &consul::Client,
+    global: &GlobalOpts,
+    args: &WatchArgs,
+) -> Result<Outcome> {
+    progress!(
+        "Watching properties from consul host: {} to key {}",
+        global.consul_host,
+        args.app_prefix
+    );
+    let yml_properties = input::load_layers(&args.input_property, None, &global.input)?;
+
+    let mut index = 0;
+    let mut reported = Vec::new();
+
+    loop {
+        let (consul_properties, new_index) =
+            consul::watch_properties(client, &args.app_prefix, index, &args.wait).await?;
+
+        // Consul may reset the index, in which case the watch starts over
+        index = if new_index < index { 0 } else { new_index };
+
+        let mut result = DiffResult::new(&yml_properties, &consul_properties, &global.compare);
+        result.sort(global.sort);
+        let difference = result.consul_properties();
+
+        let current: HashSet<_> = difference.iter().collect();
+        let previous: HashSet<_> = reported.iter().collect();
+        for item in difference.iter().filter(|item| !previous.contains(item)) {
+            outln!("{}={}", item.key, item.value);
+        }
+        for item in reported.iter().filter(|item| !current.contains(item)) {
+            outln!("Resolved {}={}", item.key, item.value);
+        }
+
+        reported = difference;
+    }
+}
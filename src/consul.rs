@@ -0,0 +1,806 @@
+use consulrs::{
+    api::{
+        self,
+        features::{Blocking, ConsistencyMode, Features},
+        kv::{
+            common::KVPair,
+            requests::{
+                DeleteKeyRequest, ReadKeyRequest, ReadKeyRequestBuilder, ReadKeysRequestBuilder,
+                SetKeyRequest,
+            },
+        },
+        EndpointMiddleware,
+    },
+    client::{ConsulClient, ConsulClientSettings, ConsulClientSettingsBuilder},
+    error::ClientError,
+    kv,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use rustify::{
+    client::Client as _, clients::reqwest::Client as HTTPClient, endpoint::Endpoint as _,
+    errors::ClientError as RestClientError,
+};
+use std::{collections::HashMap, env, fs, future::Future, time::Duration};
+
+use crate::{
+    cli::{ConnectionOpts, Consistency, Scheme},
+    error::{Error, Result},
+    output::{outln, progress, trace},
+    pattern,
+    properties::ConsulProperties,
+};
+
+mod agent;
+mod txn;
+
+pub use agent::AgentConfig;
+pub use txn::TxnOperation;
+
+/// Consul rejects transactions with more operations than this by default.
+pub const MAX_TXN_OPERATIONS: usize = 64;
+
+/// A Consul client along with the datacenter its KV requests target, which
+/// is the datacenter of the agent when not given, the consistency mode of
+/// its reads and how often failed requests are retried.
+pub struct Client {
+    consul: ConsulClient,
+    datacenter: Option<String>,
+    consistency: Consistency,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl consulrs::client::Client for Client {
+    fn http(&self) -> &HTTPClient {
+        &self.consul.http
+    }
+
+    fn middle(&self, features: Option<Features>) -> EndpointMiddleware {
+        consulrs::client::Client::middle(&self.consul, features)
+    }
+
+    fn settings(&self) -> &ConsulClientSettings {
+        &self.consul.settings
+    }
+}
+
+impl Client {
+    fn read_features(&self) -> Features {
+        let mode = match self.consistency {
+            Consistency::Default => None,
+            Consistency::Consistent => Some(ConsistencyMode::CONSISTENT),
+            Consistency::Stale => Some(ConsistencyMode::STALE),
+        };
+        Features {
+            mode,
+            ..Features::default()
+        }
+    }
+
+    fn read_request(&self) -> ReadKeyRequestBuilder {
+        let mut request = ReadKeyRequestBuilder::default();
+        request.features(self.read_features());
+        if let Some(datacenter) = &self.datacenter {
+            request.dc(datacenter);
+        }
+        request
+    }
+
+    fn keys_request(&self) -> ReadKeysRequestBuilder {
+        let mut request = ReadKeysRequestBuilder::default();
+        request.features(self.read_features());
+        if let Some(datacenter) = &self.datacenter {
+            request.dc(datacenter);
+        }
+        request
+    }
+
+    fn set_request(&self, key: &str, value: &str) -> SetKeyRequest {
+        SetKeyRequest {
+            key: key.to_string(),
+            value: value.as_bytes().to_vec(),
+            dc: self.datacenter.clone(),
+            ..SetKeyRequest::default()
+        }
+    }
+
+    fn delete_request(&self, key: &str) -> DeleteKeyRequest {
+        DeleteKeyRequest {
+            key: key.to_string(),
+            dc: self.datacenter.clone(),
+            ..DeleteKeyRequest::default()
+        }
+    }
+
+    /// Sends a request until it succeeds, fails for good or runs out of
+    /// retries, waiting twice as long before each retry. A request that is
+    /// not idempotent is only sent again when it never reached Consul.
+    async fn retry<T, F, Fut>(
+        &self,
+        context: &str,
+        idempotent: bool,
+        send: F,
+    ) -> std::result::Result<T, ClientError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = std::result::Result<T, ClientError>>,
+    {
+        let mut backoff = self.retry_backoff;
+        for attempt in 1..=self.retries {
+            match send().await {
+                Err(error) if is_transient(&error) && (idempotent || is_unsent(&error)) => {
+                    eprintln!(
+                        "{}, retrying in {:?} ({} of {})",
+                        Error::consul(context)(error),
+                        backoff,
+                        attempt,
+                        self.retries
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                res => return res,
+            }
+        }
+        send().await
+    }
+}
+
+/// Whether a request may succeed when sent again, after Consul answered
+/// that it is overloaded or failing, or the connection broke.
+fn is_transient(error: &ClientError) -> bool {
+    match error {
+        ClientError::APIError { code, .. } => *code == 429 || *code >= 500,
+        ClientError::RestClientError { source } => matches!(
+            source,
+            RestClientError::RequestError { .. } | RestClientError::ResponseError { .. }
+        ),
+        _ => false,
+    }
+}
+
+/// Whether a request failed before it was sent, because no connection to
+/// Consul could be made.
+fn is_unsent(error: &ClientError) -> bool {
+    match error {
+        ClientError::RestClientError {
+            source: RestClientError::RequestError { source, .. },
+        } => source
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_connect),
+        _ => false,
+    }
+}
+
+pub fn client(consul_host: &str, connection: &ConnectionOpts) -> Result<Client> {
+    let consul_address = address(consul_host, connection);
+    let verify = !connection.tls_skip_verify && env_flag("CONSUL_HTTP_SSL_VERIFY") != Some(false);
+
+    let mut settings = ConsulClientSettingsBuilder::default();
+    settings.address(consul_address).verify(verify);
+    if let Some(token) = token(connection)? {
+        settings.token(token);
+    }
+    let settings = settings
+        .build()
+        .map_err(|e| Error::Usage(format!("Invalid consul settings: {}", e)))?;
+
+    let http = HTTPClient::new(&settings.address, http_client(&settings, connection)?);
+    Ok(Client {
+        consul: ConsulClient { http, settings },
+        datacenter: connection.datacenter.clone(),
+        consistency: connection.consistency,
+        retries: connection.retries,
+        retry_backoff: connection.retry_backoff,
+    })
+}
+
+/// Builds the HTTP client as consulrs does, sending the namespace and admin
+/// partition headers of Consul Enterprise with every request.
+fn http_client(
+    settings: &ConsulClientSettings,
+    connection: &ConnectionOpts,
+) -> Result<reqwest::Client> {
+    // Only rustls reads a client identity from PEM files
+    let mut http_client = reqwest::Client::builder()
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(!settings.verify);
+    if let Some(timeout) = connection.timeout {
+        http_client = http_client.timeout(timeout);
+    }
+
+    if let Some(ca_cert) = &connection.ca_cert {
+        let contents = fs::read(ca_cert).map_err(|e| Error::read(ca_cert, e))?;
+        let certificate =
+            reqwest::Certificate::from_pem(&contents).map_err(|e| Error::read(ca_cert, e))?;
+        http_client = http_client.add_root_certificate(certificate);
+    }
+    if let (Some(client_cert), Some(client_key)) = (&connection.client_cert, &connection.client_key)
+    {
+        let mut contents = fs::read(client_cert).map_err(|e| Error::read(client_cert, e))?;
+        contents.extend(fs::read(client_key).map_err(|e| Error::read(client_key, e))?);
+        let identity =
+            reqwest::Identity::from_pem(&contents).map_err(|e| Error::read(client_cert, e))?;
+        http_client = http_client.identity(identity);
+    }
+
+    let mut headers = HeaderMap::new();
+    for (header, flag, value) in [
+        (
+            "X-Consul-Namespace",
+            "--consul-namespace",
+            &connection.consul_namespace,
+        ),
+        ("X-Consul-Partition", "--partition", &connection.partition),
+    ] {
+        if let Some(value) = value {
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::Usage(format!("Invalid {} {:?}: {}", flag, value, e)))?;
+            headers.insert(header, value);
+        }
+    }
+
+    http_client
+        .default_headers(headers)
+        .build()
+        .map_err(|source| ClientError::RestClientBuildError { source })
+        .map_err(Error::consul("Unable to create consul client"))
+}
+
+/// The URL of the Consul API at a host, which may be a bare name or address
+/// or include its own scheme and port, such as `https://[::1]:8501`.
+fn address(consul_host: &str, connection: &ConnectionOpts) -> String {
+    let (scheme, host) = match consul_host.split_once("://") {
+        Some((scheme, host)) => (scheme, host),
+        None => match connection.scheme {
+            Some(Scheme::Https) => ("https", consul_host),
+            Some(Scheme::Http) => ("http", consul_host),
+            None if env_flag("CONSUL_HTTP_SSL") == Some(true) => ("https", consul_host),
+            None => ("http", consul_host),
+        },
+    };
+    let host = host.trim_end_matches('/');
+
+    // The brackets of an IPv6 address hold colons of their own
+    let has_port = match host.rsplit_once(':') {
+        Some((name, port)) => {
+            port.parse::<u16>().is_ok() && (!name.contains(':') || name.ends_with(']'))
+        }
+        None => false,
+    };
+    match has_port {
+        true => format!("{}://{}", scheme, host),
+        false => format!("{}://{}:{}", scheme, host, connection.consul_port),
+    }
+}
+
+/// A boolean environment variable, as the Consul CLI reads them.
+fn env_flag(name: &str) -> Option<bool> {
+    match env::var(name).ok()?.to_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// The ACL token given on the command line or in the environment, or read
+/// from the token file.
+fn token(connection: &ConnectionOpts) -> Result<Option<String>> {
+    if let Some(token) = &connection.token {
+        return Ok(Some(token.clone()));
+    }
+    let Some(token_file) = &connection.token_file else {
+        return Ok(None);
+    };
+    let token = fs::read_to_string(token_file).map_err(|e| Error::read(token_file, e))?;
+    Ok(Some(token.trim().to_string()))
+}
+
+pub async fn load_properties(
+    client: &Client,
+    consul_host: &str,
+    app_prefix: &str,
+) -> Result<Vec<ConsulProperties>> {
+    progress!(
+        "Loading properties from consul host: {} to key {}",
+        consul_host,
+        app_prefix
+    );
+
+    to_properties(app_prefix, load_pairs(client, app_prefix).await?)
+}
+
+/// Loads the properties under the app prefix along with its folder keys,
+/// for the commands that look at the layout of the prefix itself.
+pub async fn load_entries(
+    client: &Client,
+    consul_host: &str,
+    app_prefix: &str,
+) -> Result<Vec<ConsulProperties>> {
+    progress!(
+        "Loading keys from consul host: {} to key {}",
+        consul_host,
+        app_prefix
+    );
+
+    to_entries(app_prefix, load_pairs(client, app_prefix).await?)
+}
+
+/// Expands app prefixes containing wildcards, such as `config/*-service`,
+/// into the folders that exist in Consul. Each wildcard segment is matched
+/// against the folder names one level below the segments before it.
+pub async fn expand_prefixes(client: &Client, app_prefixes: &[String]) -> Result<Vec<String>> {
+    let mut expanded = Vec::new();
+    for app_prefix in app_prefixes {
+        if !pattern::is_glob(app_prefix) {
+            expanded.push(app_prefix.clone());
+            continue;
+        }
+
+        let mut candidates = vec![String::new()];
+        for segment in app_prefix.split('/') {
+            let mut matched = Vec::new();
+            for parent in candidates {
+                if !pattern::is_glob(segment) {
+                    matched.push(format!("{}{}/", parent, segment));
+                    continue;
+                }
+                let regex = pattern::glob_to_regex(segment);
+                for folder in list_folders(client, &parent).await? {
+                    if regex.is_match(&folder) {
+                        matched.push(format!("{}{}/", parent, folder));
+                    }
+                }
+            }
+            candidates = matched;
+        }
+
+        if candidates.is_empty() {
+            progress!("No prefixes match {}", app_prefix);
+        }
+        let mut candidates: Vec<_> = candidates
+            .into_iter()
+            .map(|candidate| candidate.trim_end_matches('/').to_string())
+            .collect();
+        candidates.sort();
+        expanded.extend(candidates);
+    }
+    Ok(expanded)
+}
+
+/// Lists the names of the folders directly below a parent, which is either
+/// empty or ends with a slash.
+async fn list_folders(client: &Client, parent: &str) -> Result<Vec<String>> {
+    let mut keys_request = client.keys_request();
+    keys_request.separator("/");
+
+    let context = format!("Unable to list keys under {}", parent);
+    let keys = client.retry(&context, true, || {
+        let mut keys_request = keys_request.clone();
+        async move { kv::keys(client, parent, Some(&mut keys_request)).await }
+    });
+    let keys = match keys.await {
+        Err(ClientError::APIError { code: 404, .. }) => return Ok(Vec::new()),
+        res => res.map_err(Error::consul(context))?.response,
+    };
+
+    Ok(keys
+        .iter()
+        .filter_map(|key| key.strip_prefix(parent)?.strip_suffix('/'))
+        .filter(|folder| !folder.is_empty() && !folder.contains('/'))
+        .map(String::from)
+        .collect())
+}
+
+/// Reads every raw KV pair under the app prefix, including its flags and
+/// indexes.
+pub async fn load_pairs(client: &Client, app_prefix: &str) -> Result<Vec<KVPair>> {
+    let mut read_request = client.read_request();
+    read_request.key(app_prefix).recurse(true);
+
+    let context = format!("Unable to read {}", app_prefix);
+    let pairs = client.retry(&context, true, || {
+        let mut read_request = read_request.clone();
+        async move { kv::read(client, app_prefix, Some(&mut read_request)).await }
+    });
+
+    // Consul answers 404 when nothing exists under the prefix
+    match pairs.await {
+        Err(ClientError::APIError { code: 404, .. }) => Ok(Vec::new()),
+        res => Ok(res.map_err(Error::consul(context))?.response),
+    }
+}
+
+pub async fn load_value(client: &Client, app_prefix: &str, key: &str) -> Result<Option<String>> {
+    let key = format!("{}/{}", app_prefix, key);
+
+    let context = format!("Unable to read {}", key);
+    let pairs = client.retry(&context, true, || {
+        let key = &key;
+        async move { kv::read(client, key, Some(&mut client.read_request())).await }
+    });
+    let pairs = match pairs.await {
+        Err(ClientError::APIError { code: 404, .. }) => return Ok(None),
+        res => res.map_err(Error::consul(context))?,
+    };
+
+    match pairs.response.into_iter().next() {
+        Some(pair) => decode_value(pair).map(|(_, value)| Some(value)),
+        None => Ok(None),
+    }
+}
+
+/// Reads the app prefix with a blocking query, returning once the KV tree
+/// changes past `index` or the `wait` duration elapses, along with the new
+/// index to wait on.
+pub async fn watch_properties(
+    client: &Client,
+    app_prefix: &str,
+    index: u64,
+    wait: &str,
+) -> Result<(Vec<ConsulProperties>, u64)> {
+    let features = Features {
+        blocking: Some(Blocking {
+            index,
+            wait: Some(wait.to_string()),
+        }),
+        ..client.read_features()
+    };
+
+    let context = format!("Unable to watch {}", app_prefix);
+    let (pairs, new_index) = client
+        .retry(&context, true, || {
+            blocking_read(client, app_prefix, &features)
+        })
+        .await
+        .map_err(Error::consul(context))?;
+
+    Ok((to_properties(app_prefix, pairs)?, new_index))
+}
+
+/// Sends a blocking read of the app prefix. It goes around consulrs, which
+/// drops the index Consul sends along with the 404 for a prefix without
+/// keys, so a prefix that does not exist yet is waited on like any other.
+async fn blocking_read(
+    client: &Client,
+    app_prefix: &str,
+    features: &Features,
+) -> std::result::Result<(Vec<KVPair>, u64), ClientError> {
+    let endpoint = ReadKeyRequest {
+        key: app_prefix.to_string(),
+        recurse: Some(true),
+        dc: client.datacenter.clone(),
+        features: Some(features.clone()),
+        ..ReadKeyRequest::default()
+    };
+    let middle = consulrs::client::Client::middle(client, Some(features.clone()));
+    let request = endpoint
+        .with_middleware(&middle)
+        .request(client.consul.http.base())?;
+    let response = client.consul.http.send(request).await?;
+
+    let index = response
+        .headers()
+        .get("X-Consul-Index")
+        .and_then(|index| index.to_str().ok()?.parse().ok())
+        .unwrap_or_default();
+    match response.status().as_u16() {
+        404 => Ok((Vec::new(), index)),
+        200..=299 => serde_json::from_slice(response.body())
+            .map(|pairs| (pairs, index))
+            .map_err(|source| ClientError::JsonDeserializeError { source }),
+        code => Err(ClientError::APIError {
+            code,
+            message: String::from_utf8(response.body().clone()).ok(),
+        }),
+    }
+}
+
+/// The keys under the app prefix, relative to it. Folder keys, which end in
+/// a slash or are the app prefix's own folder key, hold no property and
+/// are left out.
+pub fn to_properties(app_prefix: &str, pairs: Vec<KVPair>) -> Result<Vec<ConsulProperties>> {
+    let mut properties = to_entries(app_prefix, pairs)?;
+    properties.retain(|item| !item.key.is_empty() && !item.key.ends_with('/'));
+    Ok(properties)
+}
+
+/// Every key under the app prefix, relative to it, folder keys included.
+pub fn to_entries(app_prefix: &str, pairs: Vec<KVPair>) -> Result<Vec<ConsulProperties>> {
+    let prefix = String::from(app_prefix);
+
+    let prefix = prefix + "/";
+
+    // A recursive read also matches sibling prefixes such as `app2` for `app`
+    pairs
+        .into_iter()
+        .filter(|item| item.key.starts_with(&prefix))
+        .map(|item| {
+            let (key, value) = decode_value(item)?;
+            trace!("Read {} from consul, {} bytes", key, value.len());
+            Ok(ConsulProperties {
+                key: key[prefix.len()..].to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Maps each key under the app prefix, relative to it, to its modify index.
+pub fn modify_indexes(app_prefix: &str, pairs: &[KVPair]) -> HashMap<String, u64> {
+    let prefix = format!("{}/", app_prefix);
+    pairs
+        .iter()
+        .filter_map(|pair| {
+            let key = pair.key.strip_prefix(&prefix)?;
+            Some((key.to_string(), pair.modify_index))
+        })
+        .collect()
+}
+
+/// Decodes the base64 value of a pair into a UTF-8 string.
+pub fn decode_value(pair: KVPair) -> Result<(String, String)> {
+    // Folder keys have no value at all
+    let value = match pair.value {
+        Some(value) => value
+            .try_into()
+            .map_err(Error::consul(format!("Unable to decode {}", pair.key)))?,
+        None => String::new(),
+    };
+    Ok((pair.key, value))
+}
+
+pub async fn write_properties(
+    client: &Client,
+    app_prefix: &str,
+    properties: &[ConsulProperties],
+) -> Result<()> {
+    for item in properties {
+        let key = format!("{}/{}", app_prefix, item.key);
+        outln!("Writing {}", key);
+
+        // Writing the same value again leaves the key as it was
+        let context = format!("Unable to write {}", key);
+        client
+            .retry(&context, true, || {
+                api::exec_with_result(client, client.set_request(&key, &item.value))
+            })
+            .await
+            .map_err(Error::consul(context))?;
+    }
+    Ok(())
+}
+
+pub async fn delete_properties(
+    client: &Client,
+    app_prefix: &str,
+    properties: &[ConsulProperties],
+) -> Result<()> {
+    for item in properties {
+        let key = format!("{}/{}", app_prefix, item.key);
+        outln!("Deleting {}", key);
+
+        let context = format!("Unable to delete {}", key);
+        client
+            .retry(&context, true, || {
+                api::exec_with_result(client, client.delete_request(&key))
+            })
+            .await
+            .map_err(Error::consul(context))?;
+    }
+    Ok(())
+}
+
+/// Applies the operations in a single Consul transaction, so either all of
+/// them are written or none are.
+pub async fn transaction(client: &Client, operations: &[TxnOperation]) -> Result<()> {
+    let request = txn::TxnRequest::new(operations, client.datacenter.clone())?;
+    // A check-and-set that already went through fails when sent again
+    let idempotent = !operations.iter().any(TxnOperation::checks_index);
+    let response = client.retry("Unable to apply transaction", idempotent, || {
+        api::exec_with_result(client, request.clone())
+    });
+
+    // A rolled back transaction is answered with 409 and the errors in the body
+    let response = match response.await {
+        Err(ClientError::APIError {
+            code: 409,
+            message: Some(message),
+        }) => serde_json::from_str(&message)
+            .map_err(|e| Error::Rejected(format!("Unable to apply transaction: {}", e)))?,
+        res => {
+            res.map_err(Error::consul("Unable to apply transaction"))?
+                .response
+        }
+    };
+
+    match response.errors.filter(|errors| !errors.is_empty()) {
+        Some(errors) => Err(Error::Rejected(format!(
+            "Consul rolled back the transaction: {}",
+            errors
+                .iter()
+                .map(|error| format!("operation {} {}", error.op_index, error.what))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Reads the configuration of the agent the client is connected to.
+pub async fn agent_config(client: &Client) -> Result<AgentConfig> {
+    let context = "Unable to reach the consul agent";
+    let res = client
+        .retry(context, true, || {
+            api::exec_with_result(client, agent::AgentSelfRequest::default())
+        })
+        .await
+        .map_err(Error::consul(context))?;
+    Ok(res.response.config)
+}
+
+/// Returns the address of the Raft leader, which is empty while the
+/// cluster has no leader.
+pub async fn leader(client: &Client) -> Result<String> {
+    let context = "Unable to read the raft leader";
+    let res = client
+        .retry(context, true, || {
+            api::exec_with_result(client, agent::StatusLeaderRequest::default())
+        })
+        .await
+        .map_err(Error::consul(context))?;
+    Ok(res.response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use clap::Parser;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Parser)]
+    struct Command {
+        #[command(flatten)]
+        connection: ConnectionOpts,
+    }
+
+    fn connection(args: &[&str]) -> ConnectionOpts {
+        Command::parse_from(["consul-help"].iter().chain(args)).connection
+    }
+
+    fn pairs(keys: &[(&str, Option<&str>)]) -> Vec<KVPair> {
+        keys.iter()
+            .map(|(key, value)| {
+                serde_json::from_value(serde_json::json!({
+                    "Key": key,
+                    "Value": value.map(|value| STANDARD.encode(value)),
+                    "CreateIndex": 1,
+                    "ModifyIndex": 1,
+                    "LockIndex": 0,
+                    "Flags": 0,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn keys(properties: &[ConsulProperties]) -> Vec<&str> {
+        properties.iter().map(|item| item.key.as_str()).collect()
+    }
+
+    #[test]
+    fn leaves_folder_keys_out_of_the_properties() {
+        let pairs = || {
+            pairs(&[
+                ("app/", None),
+                ("app/db/", None),
+                ("app/db/url", Some("jdbc:h2:mem")),
+                ("app/empty", Some("")),
+                ("app2/name", Some("other")),
+            ])
+        };
+        assert_eq!(
+            keys(&to_properties("app", pairs()).unwrap()),
+            ["db/url", "empty"]
+        );
+        assert_eq!(
+            keys(&to_entries("app", pairs()).unwrap()),
+            ["", "db/", "db/url", "empty"]
+        );
+    }
+
+    #[test]
+    fn adds_the_scheme_and_port_a_host_leaves_out() {
+        let http = connection(&["--scheme", "http"]);
+        assert_eq!(address("consul.local", &http), "http://consul.local:8500");
+        assert_eq!(address("10.0.0.1:9500/", &http), "http://10.0.0.1:9500");
+        assert_eq!(
+            address(
+                "consul.local",
+                &connection(&["--scheme", "https", "--consul-port", "8501"])
+            ),
+            "https://consul.local:8501"
+        );
+        assert_eq!(
+            address("http://consul.local", &connection(&["--scheme", "https"])),
+            "http://consul.local:8500"
+        );
+    }
+
+    #[test]
+    fn keeps_the_colons_of_ipv6_addresses_apart_from_the_port() {
+        let https = connection(&["--scheme", "https"]);
+        assert_eq!(address("[::1]", &https), "https://[::1]:8500");
+        assert_eq!(address("https://[::1]:8501", &https), "https://[::1]:8501");
+        assert_eq!(address("[fe80::1]:9500", &https), "https://[fe80::1]:9500");
+    }
+
+    fn retrying_client() -> Client {
+        // Nothing listens on port 1, so every request fails to connect
+        let connection = connection(&[
+            "--scheme",
+            "http",
+            "--consul-port",
+            "1",
+            "--retries",
+            "2",
+            "--retry-backoff",
+            "0ms",
+        ]);
+        client("127.0.0.1", &connection).unwrap()
+    }
+
+    async fn attempts<F, Fut>(client: &Client, idempotent: bool, send: F) -> u32
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = std::result::Result<(), ClientError>>,
+    {
+        let attempts = AtomicU32::new(0);
+        let res = client
+            .retry("test", idempotent, || {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                send()
+            })
+            .await;
+        assert!(res.is_err());
+        attempts.into_inner()
+    }
+
+    fn server_error() -> std::result::Result<(), ClientError> {
+        Err(ClientError::APIError {
+            code: 500,
+            message: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests_on_transient_errors_only() {
+        let client = retrying_client();
+        assert_eq!(
+            attempts(&client, true, || async { server_error() }).await,
+            3
+        );
+        let not_found = || async {
+            Err(ClientError::APIError {
+                code: 404,
+                message: None,
+            })
+        };
+        assert_eq!(attempts(&client, true, not_found).await, 1);
+    }
+
+    #[tokio::test]
+    async fn resends_other_requests_only_when_they_were_never_sent() {
+        let client = retrying_client();
+        assert_eq!(
+            attempts(&client, false, || async { server_error() }).await,
+            1
+        );
+        let unsent = || async {
+            let request = client.set_request("app/key", "value");
+            api::exec_with_result(&client, request).await.map(|_| ())
+        };
+        assert_eq!(attempts(&client, false, unsent).await, 3);
+    }
+}
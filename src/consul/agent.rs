@@ -0,0 +1,59 @@
+use consulrs::api::features::{FeaturedEndpoint, Features};
+use rustify_derive::Endpoint;
+use serde::Deserialize;
+
+/// ## Read Configuration
+/// This endpoint returns the configuration and member information of the
+/// local agent.
+///
+/// * Path: agent/self
+/// * Method: GET
+/// * Response: [AgentSelfResponse]
+/// * Reference: https://developer.hashicorp.com/consul/api-docs/agent#read-configuration
+#[derive(Debug, Default, Endpoint)]
+#[endpoint(path = "agent/self", response = "AgentSelfResponse")]
+pub struct AgentSelfRequest {
+    #[endpoint(skip)]
+    pub features: Option<Features>,
+}
+
+impl FeaturedEndpoint for AgentSelfRequest {
+    fn features(&self) -> Option<Features> {
+        self.features.clone()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentSelfResponse {
+    pub config: AgentConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AgentConfig {
+    pub datacenter: String,
+    pub node_name: String,
+    pub version: String,
+}
+
+/// ## Get Raft Leader
+/// This endpoint returns the Raft leader for the datacenter in which the
+/// agent is running.
+///
+/// * Path: status/leader
+/// * Method: GET
+/// * Response: String
+/// * Reference: https://developer.hashicorp.com/consul/api-docs/status#get-raft-leader
+#[derive(Debug, Default, Endpoint)]
+#[endpoint(path = "status/leader", response = "String")]
+pub struct StatusLeaderRequest {
+    #[endpoint(skip)]
+    pub features: Option<Features>,
+}
+
+impl FeaturedEndpoint for StatusLeaderRequest {
+    fn features(&self) -> Option<Features> {
+        self.features.clone()
+    }
+}
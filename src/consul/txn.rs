@@ -0,0 +1,126 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use consulrs::api::features::{FeaturedEndpoint, Features};
+use rustify_derive::Endpoint;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{self, Error};
+
+/// ## Transaction
+/// This endpoint applies a list of KV operations atomically.
+///
+/// * Path: txn
+/// * Method: PUT
+/// * Response: [TxnResponse]
+/// * Reference: https://developer.hashicorp.com/consul/api-docs/txn
+#[derive(Debug, Clone, Endpoint)]
+#[endpoint(path = "txn", method = "PUT", response = "TxnResponse")]
+pub struct TxnRequest {
+    #[endpoint(skip)]
+    pub features: Option<Features>,
+    #[endpoint(raw)]
+    pub operations: Vec<u8>,
+    #[endpoint(query)]
+    pub dc: Option<String>,
+}
+
+impl FeaturedEndpoint for TxnRequest {
+    fn features(&self) -> Option<Features> {
+        self.features.clone()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TxnResponse {
+    pub errors: Option<Vec<TxnError>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TxnError {
+    pub op_index: usize,
+    pub what: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TxnOperation {
+    #[serde(rename = "KV")]
+    pub kv: TxnKv,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TxnKv {
+    pub verb: &'static str,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+}
+
+impl TxnOperation {
+    pub fn set(key: String, value: &str) -> TxnOperation {
+        TxnOperation {
+            kv: TxnKv {
+                verb: "set",
+                key,
+                value: Some(STANDARD.encode(value)),
+                flags: None,
+                index: None,
+            },
+        }
+    }
+
+    pub fn with_flags(mut self, flags: u64) -> TxnOperation {
+        self.kv.flags = Some(flags);
+        self
+    }
+
+    pub fn delete(key: String) -> TxnOperation {
+        TxnOperation {
+            kv: TxnKv {
+                verb: "delete",
+                key,
+                value: None,
+                flags: None,
+                index: None,
+            },
+        }
+    }
+
+    /// Sets the key only if its modify index still matches, where an index
+    /// of 0 means the key must not exist yet.
+    pub fn cas(key: String, value: &str, index: u64) -> TxnOperation {
+        let mut operation = TxnOperation::set(key, value);
+        operation.kv.verb = "cas";
+        operation.kv.index = Some(index);
+        operation
+    }
+
+    /// Deletes the key only if its modify index still matches.
+    pub fn delete_cas(key: String, index: u64) -> TxnOperation {
+        let mut operation = TxnOperation::delete(key);
+        operation.kv.verb = "delete-cas";
+        operation.kv.index = Some(index);
+        operation
+    }
+
+    /// Whether the operation only applies while the modify index of its key
+    /// still matches.
+    pub fn checks_index(&self) -> bool {
+        self.kv.index.is_some()
+    }
+}
+
+impl TxnRequest {
+    pub fn new(operations: &[TxnOperation], dc: Option<String>) -> error::Result<TxnRequest> {
+        Ok(TxnRequest {
+            features: None,
+            operations: serde_json::to_vec(operations).map_err(Error::serialize)?,
+            dc,
+        })
+    }
+}
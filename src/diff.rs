@@ -0,0 +1,948 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    mem,
+};
+
+use crate::{
+    cli::{CompareOpts, EmptyValues, Normalization, Placeholders, SortOrder, ValueTypes},
+    mapping, placeholder,
+    properties::ConsulProperties,
+};
+
+/// The differences between the input properties and Consul, by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffResult {
+    pub only_in_file: Vec<ConsulProperties>,
+    pub only_in_consul: Vec<ConsulProperties>,
+    /// Keys on both sides with different values, as the key, the file value
+    /// and the Consul value
+    pub changed: Vec<(String, String, String)>,
+    /// Keys with the same value on both sides, for the formats that show
+    /// them as context
+    pub unchanged: Vec<ConsulProperties>,
+    /// Differences in keys of the ignore file, which are expected and
+    /// reported apart
+    pub ignored: Vec<Change>,
+    /// Keys matched with --ignore-key-case whose casing differs, as the
+    /// Consul key and the file key
+    pub case_mismatches: Vec<(String, String)>,
+    /// Keys only in Consul with the same value as a key only in the file,
+    /// as the Consul key, the file key and the value
+    pub renamed: Vec<(String, String, String)>,
+    /// Keys on both sides whose file value holds a placeholder, such as
+    /// `${DB_PASSWORD}`, that cannot be compared with the resolved value in
+    /// Consul, as the key, the file value and the Consul value
+    pub unresolvable: Vec<(String, String, String)>,
+    /// Keys with an empty value in Consul set apart with `--empty-values
+    /// separate`, as the key and its value in the file if it has one
+    pub empty_in_consul: Vec<(String, Option<String>)>,
+}
+
+impl DiffResult {
+    pub fn new(
+        file_properties: &[ConsulProperties],
+        consul_properties: &[ConsulProperties],
+        compare_opts: &CompareOpts,
+    ) -> DiffResult {
+        let prepared = prepare(file_properties, consul_properties, compare_opts);
+        let (file_properties, consul_properties) = (&prepared.file[..], &prepared.consul[..]);
+
+        let mut result = DiffResult {
+            empty_in_consul: prepared.empty_in_consul,
+            ..DiffResult::default()
+        };
+        let changes = match_properties(file_properties, consul_properties, compare_opts);
+        let changed_keys: HashSet<_> = changes
+            .iter()
+            .filter(|change| matches!(change, Change::Changed { .. }))
+            .map(|change| change.key().to_string())
+            .collect();
+        for change in changes {
+            if change.is_ignored(compare_opts) {
+                result.ignored.push(change);
+                continue;
+            }
+            match change {
+                Change::OnlyInFile(item) => result.only_in_file.push(item),
+                Change::OnlyInConsul(item) => result.only_in_consul.push(item),
+                Change::Changed {
+                    key,
+                    file_value,
+                    consul_value,
+                } if compare_opts.placeholders.is_some() && placeholder::contains(&file_value) => {
+                    result.unresolvable.push((key, file_value, consul_value))
+                }
+                Change::Changed {
+                    key,
+                    file_value,
+                    consul_value,
+                } => result.changed.push((key, file_value, consul_value)),
+            }
+        }
+
+        let file_keys: HashSet<_> = selected(file_properties, compare_opts)
+            .into_iter()
+            .map(|item| canonical_key(&item.key, compare_opts))
+            .collect();
+        result.unchanged = selected(consul_properties, compare_opts)
+            .into_iter()
+            .filter(|item| {
+                file_keys.contains(&canonical_key(&item.key, compare_opts))
+                    && !changed_keys.contains(&item.key)
+            })
+            .cloned()
+            .collect();
+
+        if compare_opts.ignore_key_case {
+            let consul_keys: HashMap<_, _> = selected(consul_properties, compare_opts)
+                .into_iter()
+                .map(|item| (canonical_key(&item.key, compare_opts), item.key.as_str()))
+                .collect();
+            result.case_mismatches = selected(file_properties, compare_opts)
+                .into_iter()
+                .filter_map(|item| {
+                    let consul_key = consul_keys.get(&canonical_key(&item.key, compare_opts))?;
+                    (*consul_key != item.key
+                        && consul_key.to_lowercase() == item.key.to_lowercase())
+                    .then(|| (consul_key.to_string(), item.key.clone()))
+                })
+                .collect();
+        }
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether there is anything besides differences to report: ignored,
+    /// unresolvable or empty keys, or keys whose casing differs.
+    pub fn has_remarks(&self) -> bool {
+        !(self.ignored.is_empty()
+            && self.case_mismatches.is_empty()
+            && self.unresolvable.is_empty()
+            && self.empty_in_consul.is_empty())
+    }
+
+    pub fn len(&self) -> usize {
+        self.only_in_file.len()
+            + self.only_in_consul.len()
+            + self.changed.len()
+            + self.renamed.len()
+    }
+
+    /// The number of keys in Consul, differing or not.
+    pub fn consul_len(&self) -> usize {
+        let ignored = self
+            .ignored
+            .iter()
+            .filter(|change| !matches!(change, Change::OnlyInFile(_)))
+            .count();
+        self.only_in_consul.len()
+            + self.changed.len()
+            + self.unchanged.len()
+            + self.renamed.len()
+            + self.unresolvable.len()
+            + ignored
+    }
+
+    /// The number of keys in the file, differing or not.
+    pub fn file_len(&self) -> usize {
+        let ignored = self
+            .ignored
+            .iter()
+            .filter(|change| !matches!(change, Change::OnlyInConsul(_)))
+            .count();
+        self.only_in_file.len()
+            + self.changed.len()
+            + self.unchanged.len()
+            + self.renamed.len()
+            + self.unresolvable.len()
+            + ignored
+    }
+
+    /// The share of all the keys on either side that are the same on both,
+    /// from 0 to 100.
+    pub fn in_sync_percent(&self) -> f64 {
+        let total = self.len() + self.unchanged.len() + self.unresolvable.len();
+        if total == 0 {
+            return 100.0;
+        }
+        self.unchanged.len() as f64 * 100.0 / total as f64
+    }
+
+    /// Pairs a key only in Consul with a key only in the file when they are
+    /// the only ones with a value on both sides, as a probable rename, the
+    /// way git detects renamed files. Empty values are too common to tell.
+    pub fn detect_renames(&mut self) {
+        let mut file_values: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (position, item) in self.only_in_file.iter().enumerate() {
+            file_values.entry(&item.value).or_default().push(position);
+        }
+        let mut consul_values: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (position, item) in self.only_in_consul.iter().enumerate() {
+            consul_values.entry(&item.value).or_default().push(position);
+        }
+
+        let mut renamed_file = HashSet::new();
+        let mut renamed_consul = HashSet::new();
+        for (position, item) in self.only_in_consul.iter().enumerate() {
+            if item.value.is_empty() || consul_values[item.value.as_str()].len() != 1 {
+                continue;
+            }
+            if let Some([file_position]) = file_values.get(item.value.as_str()).map(Vec::as_slice) {
+                let file_item = &self.only_in_file[*file_position];
+                self.renamed
+                    .push((item.key.clone(), file_item.key.clone(), item.value.clone()));
+                renamed_file.insert(*file_position);
+                renamed_consul.insert(position);
+            }
+        }
+
+        let remaining = |items: Vec<ConsulProperties>, renamed: &HashSet<usize>| {
+            items
+                .into_iter()
+                .enumerate()
+                .filter(|(position, _)| !renamed.contains(position))
+                .map(|(_, item)| item)
+                .collect()
+        };
+        self.only_in_file = remaining(mem::take(&mut self.only_in_file), &renamed_file);
+        self.only_in_consul = remaining(mem::take(&mut self.only_in_consul), &renamed_consul);
+    }
+
+    /// The Consul side of every difference, which is what the output file
+    /// holds.
+    pub fn consul_properties(&self) -> Vec<ConsulProperties> {
+        self.only_in_consul
+            .iter()
+            .cloned()
+            .chain(
+                self.changed
+                    .iter()
+                    .map(|(key, _, consul_value)| ConsulProperties {
+                        key: key.clone(),
+                        value: consul_value.clone(),
+                    }),
+            )
+            .chain(
+                self.renamed
+                    .iter()
+                    .map(|(consul_key, _, value)| ConsulProperties {
+                        key: consul_key.clone(),
+                        value: value.clone(),
+                    }),
+            )
+            .collect()
+    }
+
+    /// Prefixes every key, so the results of several app prefixes can be
+    /// told apart once combined.
+    pub fn with_prefix(self, app_prefix: &str) -> DiffResult {
+        let prefixed = |key: String| format!("{}/{}", app_prefix, key);
+        let prefix_items = |items: Vec<ConsulProperties>| {
+            items
+                .into_iter()
+                .map(|item| ConsulProperties {
+                    key: prefixed(item.key),
+                    value: item.value,
+                })
+                .collect()
+        };
+        DiffResult {
+            only_in_file: prefix_items(self.only_in_file),
+            only_in_consul: prefix_items(self.only_in_consul),
+            changed: self
+                .changed
+                .into_iter()
+                .map(|(key, file_value, consul_value)| (prefixed(key), file_value, consul_value))
+                .collect(),
+            unchanged: prefix_items(self.unchanged),
+            ignored: self
+                .ignored
+                .into_iter()
+                .map(|change| change.with_prefix(app_prefix))
+                .collect(),
+            case_mismatches: self
+                .case_mismatches
+                .into_iter()
+                .map(|(consul_key, file_key)| (prefixed(consul_key), prefixed(file_key)))
+                .collect(),
+            renamed: self
+                .renamed
+                .into_iter()
+                .map(|(consul_key, file_key, value)| {
+                    (prefixed(consul_key), prefixed(file_key), value)
+                })
+                .collect(),
+            unresolvable: self
+                .unresolvable
+                .into_iter()
+                .map(|(key, file_value, consul_value)| (prefixed(key), file_value, consul_value))
+                .collect(),
+            empty_in_consul: self
+                .empty_in_consul
+                .into_iter()
+                .map(|(key, file_value)| (prefixed(key), file_value))
+                .collect(),
+        }
+    }
+
+    /// Splits the result by the first segment of each key, such as
+    /// `datasource` for `datasource/url` or `servers` for `servers[0]`, in
+    /// the order of the segments. Keys without a folder are grouped under an
+    /// empty name.
+    pub fn group_by_namespace(&self) -> Vec<(String, DiffResult)> {
+        self.group_by(|key| match key.split_once(['/', '[']) {
+            Some((first, _)) => first.to_string(),
+            None => String::new(),
+        })
+    }
+
+    /// Splits the result by a group of each key, in the order of the groups.
+    pub fn group_by<G: Ord>(&self, group_of: impl Fn(&str) -> G) -> Vec<(G, DiffResult)> {
+        let mut groups: BTreeMap<G, DiffResult> = BTreeMap::new();
+        for item in &self.only_in_file {
+            let group = groups.entry(group_of(&item.key)).or_default();
+            group.only_in_file.push(item.clone());
+        }
+        for item in &self.only_in_consul {
+            let group = groups.entry(group_of(&item.key)).or_default();
+            group.only_in_consul.push(item.clone());
+        }
+        for change in &self.changed {
+            let group = groups.entry(group_of(&change.0)).or_default();
+            group.changed.push(change.clone());
+        }
+        for item in &self.unchanged {
+            let group = groups.entry(group_of(&item.key)).or_default();
+            group.unchanged.push(item.clone());
+        }
+        for change in &self.ignored {
+            let group = groups.entry(group_of(change.key())).or_default();
+            group.ignored.push(change.clone());
+        }
+        for mismatch in &self.case_mismatches {
+            let group = groups.entry(group_of(&mismatch.0)).or_default();
+            group.case_mismatches.push(mismatch.clone());
+        }
+        for rename in &self.renamed {
+            let group = groups.entry(group_of(&rename.0)).or_default();
+            group.renamed.push(rename.clone());
+        }
+        for change in &self.unresolvable {
+            let group = groups.entry(group_of(&change.0)).or_default();
+            group.unresolvable.push(change.clone());
+        }
+        for empty in &self.empty_in_consul {
+            let group = groups.entry(group_of(&empty.0)).or_default();
+            group.empty_in_consul.push(empty.clone());
+        }
+        groups.into_iter().collect()
+    }
+
+    /// Sorts the keys of each kind of difference, unless in input order.
+    pub fn sort(&mut self, sort_order: SortOrder) {
+        if sort_order == SortOrder::None {
+            return;
+        }
+        self.only_in_file.sort_by(|a, b| a.key.cmp(&b.key));
+        self.only_in_consul.sort_by(|a, b| a.key.cmp(&b.key));
+        self.changed.sort_by(|a, b| a.0.cmp(&b.0));
+        self.unchanged.sort_by(|a, b| a.key.cmp(&b.key));
+        self.ignored.sort_by(|a, b| a.key().cmp(b.key()));
+        self.case_mismatches.sort();
+        self.renamed.sort();
+        self.unresolvable.sort_by(|a, b| a.0.cmp(&b.0));
+        self.empty_in_consul.sort();
+    }
+
+    pub fn extend(&mut self, other: DiffResult) {
+        self.only_in_file.extend(other.only_in_file);
+        self.only_in_consul.extend(other.only_in_consul);
+        self.changed.extend(other.changed);
+        self.unchanged.extend(other.unchanged);
+        self.ignored.extend(other.ignored);
+        self.case_mismatches.extend(other.case_mismatches);
+        self.renamed.extend(other.renamed);
+        self.unresolvable.extend(other.unresolvable);
+        self.empty_in_consul.extend(other.empty_in_consul);
+    }
+}
+
+/// The properties whose keys match an --include pattern, if any, and no
+/// --exclude pattern.
+fn selected<'a>(
+    properties: &'a [ConsulProperties],
+    compare_opts: &CompareOpts,
+) -> Vec<&'a ConsulProperties> {
+    properties
+        .iter()
+        .filter(|item| {
+            (compare_opts.include.is_empty()
+                || compare_opts
+                    .include
+                    .iter()
+                    .any(|regex| regex.is_match(&item.key)))
+                && !compare_opts
+                    .exclude
+                    .iter()
+                    .any(|regex| regex.is_match(&item.key))
+        })
+        .collect()
+}
+
+/// The form keys are matched by, which only differs from the key itself
+/// with the --spring-compat, --relaxed-binding or --ignore-key-case options.
+fn canonical_key(key: &str, compare_opts: &CompareOpts) -> String {
+    let key = if compare_opts.spring_compat {
+        key.replace('.', "/")
+    } else {
+        key.to_string()
+    };
+
+    if compare_opts.relaxed_binding {
+        key.chars()
+            .filter(|character| *character != '-' && *character != '_')
+            .flat_map(char::to_lowercase)
+            .collect()
+    } else if compare_opts.ignore_key_case {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// Parses a value holding a JSON object or array. Scalars are left to the
+/// other comparisons, so `1` and `1.0` still differ unless coerced.
+pub fn json_document(value: &str) -> Option<serde_json::Value> {
+    match value.trim_start().as_bytes().first() {
+        Some(b'{' | b'[') => serde_json::from_str(value).ok(),
+        _ => None,
+    }
+}
+
+/// Whether two values are the same once normalized, as strings unless
+/// --value-types coerces them. JSON objects and arrays are the same when
+/// they hold the same document, whatever their key order and whitespace.
+/// Numbers within --float-tolerance of each other are the same. With
+/// --unordered-lists, comma separated values are the same when they hold
+/// the same items in any order.
+fn same_value(file_value: &str, consul_value: &str, compare_opts: &CompareOpts) -> bool {
+    let file_value = normalized_value(file_value, &compare_opts.normalize);
+    let consul_value = normalized_value(consul_value, &compare_opts.normalize);
+    if let (Some(file_document), Some(consul_document)) =
+        (json_document(&file_value), json_document(&consul_value))
+    {
+        return file_document == consul_document;
+    }
+    if compare_opts.unordered_lists && (file_value.contains(',') || consul_value.contains(',')) {
+        let items = |value: &str| {
+            let mut items: Vec<_> = value
+                .split(',')
+                .map(|item| comparable_value(item.trim(), compare_opts).into_owned())
+                .collect();
+            items.sort();
+            items
+        };
+        return items(&file_value) == items(&consul_value);
+    }
+    if let Some(tolerance) = compare_opts.float_tolerance {
+        if let (Ok(file_number), Ok(consul_number)) = (
+            file_value.trim().parse::<f64>(),
+            consul_value.trim().parse::<f64>(),
+        ) {
+            if (file_number - consul_number).abs() <= tolerance {
+                return true;
+            }
+        }
+    }
+    comparable_value(&file_value, compare_opts) == comparable_value(&consul_value, compare_opts)
+}
+
+/// The form a normalized value is compared in.
+fn comparable_value<'a>(value: &'a str, compare_opts: &CompareOpts) -> Cow<'a, str> {
+    match compare_opts.value_types {
+        ValueTypes::Strict => Cow::Borrowed(value),
+        ValueTypes::Coerce => coerced_value(value),
+    }
+}
+
+/// The array and the index of a key flattened from an element of a list of
+/// scalars, such as `servers` and `1` for `servers[1]`.
+fn array_element(key: &str) -> Option<(&str, usize)> {
+    let (array, index) = key.strip_suffix(']')?.rsplit_once('[')?;
+    Some((array, index.parse().ok()?))
+}
+
+/// The arrays of scalars whose elements are the same on both sides, in any
+/// order, so reordering them is not a difference.
+fn reordered_arrays(
+    file_properties: &[&ConsulProperties],
+    consul_properties: &[&ConsulProperties],
+    compare_opts: &CompareOpts,
+) -> HashSet<String> {
+    let elements = |properties: &[&ConsulProperties]| {
+        let mut arrays: HashMap<String, Vec<String>> = HashMap::new();
+        for item in properties {
+            let key = canonical_key(&item.key, compare_opts);
+            if let Some((array, _)) = array_element(&key) {
+                let value = normalized_value(&item.value, &compare_opts.normalize);
+                arrays
+                    .entry(array.to_string())
+                    .or_default()
+                    .push(comparable_value(&value, compare_opts).into_owned());
+            }
+        }
+        for values in arrays.values_mut() {
+            values.sort();
+        }
+        arrays
+    };
+
+    let file_arrays = elements(file_properties);
+    let consul_arrays = elements(consul_properties);
+    file_arrays
+        .into_iter()
+        .filter(|(array, values)| consul_arrays.get(array) == Some(values))
+        .map(|(array, _)| array)
+        .collect()
+}
+
+/// Applies the --normalize steps to a value, line endings first so trimming
+/// also strips a trailing `\r`, and quotes last so the ones inside
+/// whitespace are found.
+fn normalized_value<'a>(value: &'a str, steps: &[Normalization]) -> Cow<'a, str> {
+    let enabled = |step| steps.contains(&step) || steps.contains(&Normalization::All);
+
+    let mut value = Cow::Borrowed(value);
+    if enabled(Normalization::Newlines) && value.contains("\r\n") {
+        value = Cow::Owned(value.replace("\r\n", "\n"));
+    }
+    if enabled(Normalization::Trim) && value.trim() != value {
+        value = Cow::Owned(value.trim().to_string());
+    }
+    if enabled(Normalization::Dequote) {
+        let dequoted = ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .map(str::to_string);
+        if let Some(dequoted) = dequoted {
+            value = Cow::Owned(dequoted);
+        }
+    }
+    value
+}
+
+/// The canonical form of a value that reads as a number or a boolean, so
+/// `08080`, `8080` and `8080.0` or `True` and `true` compare the same.
+fn coerced_value(value: &str) -> Cow<'_, str> {
+    let trimmed = value.trim();
+    if trimmed.eq_ignore_ascii_case("true") || trimmed.eq_ignore_ascii_case("false") {
+        return Cow::Owned(trimmed.to_ascii_lowercase());
+    }
+    if let Ok(integer) = trimmed.parse::<i128>() {
+        return Cow::Owned(integer.to_string());
+    }
+    match trimmed.parse::<f64>() {
+        // Names such as `inf` and `NaN` parse as floats but are words here
+        Ok(float) if float.is_finite() => Cow::Owned(float.to_string()),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    OnlyInFile(ConsulProperties),
+    OnlyInConsul(ConsulProperties),
+    Changed {
+        key: String,
+        file_value: String,
+        consul_value: String,
+    },
+}
+
+impl Change {
+    pub fn key(&self) -> &str {
+        match self {
+            Change::OnlyInFile(item) | Change::OnlyInConsul(item) => &item.key,
+            Change::Changed { key, .. } => key,
+        }
+    }
+
+    /// The file value and the Consul value, on the sides that have the key.
+    pub fn values(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            Change::OnlyInFile(item) => (Some(&item.value), None),
+            Change::OnlyInConsul(item) => (None, Some(&item.value)),
+            Change::Changed {
+                file_value,
+                consul_value,
+                ..
+            } => (Some(file_value), Some(consul_value)),
+        }
+    }
+
+    /// Whether a rule of the ignore file covers the change.
+    fn is_ignored(&self, compare_opts: &CompareOpts) -> bool {
+        let (file_value, consul_value) = self.values();
+        compare_opts
+            .ignore_rules
+            .iter()
+            .any(|rule| rule.matches(self.key(), file_value, consul_value))
+    }
+
+    fn with_prefix(self, app_prefix: &str) -> Change {
+        let prefixed = |key: String| format!("{}/{}", app_prefix, key);
+        match self {
+            Change::OnlyInFile(item) => Change::OnlyInFile(ConsulProperties {
+                key: prefixed(item.key),
+                value: item.value,
+            }),
+            Change::OnlyInConsul(item) => Change::OnlyInConsul(ConsulProperties {
+                key: prefixed(item.key),
+                value: item.value,
+            }),
+            Change::Changed {
+                key,
+                file_value,
+                consul_value,
+            } => Change::Changed {
+                key: prefixed(key),
+                file_value,
+                consul_value,
+            },
+        }
+    }
+}
+
+/// The properties on each side as they are compared: the file keys renamed
+/// by --key-map and their placeholders resolved with `--placeholders
+/// resolve`, and the empty Consul keys set apart by --empty-values.
+struct Prepared {
+    file: Vec<ConsulProperties>,
+    consul: Vec<ConsulProperties>,
+    empty_in_consul: Vec<(String, Option<String>)>,
+}
+
+fn prepare(
+    file_properties: &[ConsulProperties],
+    consul_properties: &[ConsulProperties],
+    compare_opts: &CompareOpts,
+) -> Prepared {
+    let mut file_properties = match compare_opts.key_mappings.is_empty() {
+        true => file_properties.to_vec(),
+        false => mapping::apply(file_properties, &compare_opts.key_mappings),
+    };
+    if compare_opts.placeholders == Some(Placeholders::Resolve) {
+        for item in &mut file_properties {
+            item.value = placeholder::resolve(&item.value).into_owned();
+        }
+    }
+
+    if compare_opts.empty_values == EmptyValues::Present {
+        return Prepared {
+            file: file_properties,
+            consul: consul_properties.to_vec(),
+            empty_in_consul: Vec::new(),
+        };
+    }
+    let (empty, consul_properties): (Vec<_>, Vec<_>) =
+        consul_properties.iter().cloned().partition(|item| {
+            item.value.is_empty() && !item.key.is_empty() && !item.key.ends_with('/')
+        });
+    if compare_opts.empty_values == EmptyValues::Missing {
+        return Prepared {
+            file: file_properties,
+            consul: consul_properties,
+            empty_in_consul: Vec::new(),
+        };
+    }
+
+    // The file side of an empty key is reported along with it
+    let file_values: HashMap<_, _> = file_properties
+        .iter()
+        .map(|item| (canonical_key(&item.key, compare_opts), &item.value))
+        .collect();
+    let empty_in_consul = selected(&empty, compare_opts)
+        .into_iter()
+        .map(|item| {
+            let file_value = file_values.get(&canonical_key(&item.key, compare_opts));
+            (item.key.clone(), file_value.map(|value| value.to_string()))
+        })
+        .collect();
+    let empty_keys: HashSet<_> = empty
+        .iter()
+        .map(|item| canonical_key(&item.key, compare_opts))
+        .collect();
+    file_properties.retain(|item| !empty_keys.contains(&canonical_key(&item.key, compare_opts)));
+    Prepared {
+        file: file_properties,
+        consul: consul_properties,
+        empty_in_consul,
+    }
+}
+
+/// Matches the file keys to the Consul keys the way diff does, after
+/// --key-map, --placeholders and --empty-values, leaving out the changes
+/// the ignore file covers. A changed key is reported under its Consul key,
+/// so writing it updates the key that is already there, and a file value
+/// whose placeholder is left unresolved never replaces the value in Consul.
+pub fn compare_properties(
+    file_properties: &[ConsulProperties],
+    consul_properties: &[ConsulProperties],
+    compare_opts: &CompareOpts,
+) -> Vec<Change> {
+    let prepared = prepare(file_properties, consul_properties, compare_opts);
+    match_properties(&prepared.file, &prepared.consul, compare_opts)
+        .into_iter()
+        .filter(|change| !change.is_ignored(compare_opts))
+        .filter(|change| match change {
+            Change::Changed { file_value, .. } => {
+                compare_opts.placeholders.is_none() || !placeholder::contains(file_value)
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+fn match_properties(
+    file_properties: &[ConsulProperties],
+    consul_properties: &[ConsulProperties],
+    compare_opts: &CompareOpts,
+) -> Vec<Change> {
+    let file_properties = selected(file_properties, compare_opts);
+    let consul_properties = selected(consul_properties, compare_opts);
+
+    let consul_items: HashMap<_, _> = consul_properties
+        .iter()
+        .map(|item| (canonical_key(&item.key, compare_opts), *item))
+        .collect();
+    let file_keys: HashSet<_> = file_properties
+        .iter()
+        .map(|item| canonical_key(&item.key, compare_opts))
+        .collect();
+
+    let reordered = match compare_opts.unordered_lists {
+        true => reordered_arrays(&file_properties, &consul_properties, compare_opts),
+        false => HashSet::new(),
+    };
+
+    let mut changes = Vec::new();
+    for item in file_properties {
+        let key = canonical_key(&item.key, compare_opts);
+        match consul_items.get(&key) {
+            None => changes.push(Change::OnlyInFile(item.clone())),
+            Some(_) if array_element(&key).is_some_and(|(array, _)| reordered.contains(array)) => {}
+            Some(consul_item) if !same_value(&item.value, &consul_item.value, compare_opts) => {
+                changes.push(Change::Changed {
+                    key: consul_item.key.clone(),
+                    file_value: item.value.clone(),
+                    consul_value: consul_item.value.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for item in consul_properties {
+        if !file_keys.contains(&canonical_key(&item.key, compare_opts)) {
+            changes.push(Change::OnlyInConsul(item.clone()));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore;
+    use clap::Parser;
+    use std::{env, fs, process};
+
+    #[derive(Parser)]
+    struct Command {
+        #[command(flatten)]
+        compare_opts: CompareOpts,
+    }
+
+    fn compare_opts(args: &[&str]) -> CompareOpts {
+        Command::parse_from(["consul-help"].iter().chain(args)).compare_opts
+    }
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn compares_values_as_strings_by_default() {
+        let opts = compare_opts(&[]);
+        assert!(same_value("8080", "8080", &opts));
+        assert!(!same_value("8080", "8080.0", &opts));
+        assert!(!same_value("True", "true", &opts));
+        assert!(!same_value(" a", "a", &opts));
+    }
+
+    #[test]
+    fn compares_json_documents_whatever_their_layout() {
+        let opts = compare_opts(&[]);
+        assert!(same_value(
+            r#"{"a": 1, "b": [1, 2]}"#,
+            r#"{"b":[1,2],"a":1}"#,
+            &opts
+        ));
+        assert!(!same_value("[1, 2]", "[2, 1]", &opts));
+    }
+
+    #[test]
+    fn coerces_numbers_and_booleans() {
+        let opts = compare_opts(&["--value-types", "coerce"]);
+        assert!(same_value("08080", "8080.0", &opts));
+        assert!(same_value("True", "true", &opts));
+        assert!(!same_value("inf", "INF", &opts));
+    }
+
+    #[test]
+    fn applies_the_float_tolerance() {
+        let opts = compare_opts(&["--float-tolerance", "0.01"]);
+        assert!(same_value("0.1", "0.105", &opts));
+        assert!(!same_value("0.1", "0.2", &opts));
+    }
+
+    #[test]
+    fn compares_unordered_lists() {
+        let opts = compare_opts(&["--unordered-lists"]);
+        assert!(same_value("a, b,c", "c,b,a", &opts));
+        assert!(!same_value("a,b", "a,c", &opts));
+    }
+
+    #[test]
+    fn compares_normalized_values() {
+        let opts = compare_opts(&["--normalize"]);
+        assert!(same_value(" \"a\r\nb\" ", "a\nb", &opts));
+        let opts = compare_opts(&["--normalize", "trim"]);
+        assert!(!same_value("'a'", "a", &opts));
+    }
+
+    #[test]
+    fn spring_compat_keys_use_slashes() {
+        assert_eq!(canonical_key("Db.Url", &compare_opts(&[])), "Db.Url");
+        assert_eq!(
+            canonical_key("Db.Url", &compare_opts(&["--spring-compat"])),
+            "Db/Url"
+        );
+    }
+
+    #[test]
+    fn relaxed_binding_drops_case_dashes_and_underscores() {
+        assert_eq!(
+            canonical_key("Db/Max_Pool-Size", &compare_opts(&["--relaxed-binding"])),
+            "db/maxpoolsize"
+        );
+    }
+
+    #[test]
+    fn ignore_key_case_lowercases_keys() {
+        assert_eq!(
+            canonical_key("Db/Max_Pool-Size", &compare_opts(&["--ignore-key-case"])),
+            "db/max_pool-size"
+        );
+    }
+
+    #[test]
+    fn reports_changes_under_the_consul_key() {
+        let changes = compare_properties(
+            &[property("db.url", "b"), property("name", "app")],
+            &[property("db/url", "a"), property("extra", "x")],
+            &compare_opts(&["--spring-compat"]),
+        );
+        assert!(changes.contains(&Change::Changed {
+            key: "db/url".to_string(),
+            file_value: "b".to_string(),
+            consul_value: "a".to_string(),
+        }));
+        assert!(changes.contains(&Change::OnlyInFile(property("name", "app"))));
+        assert!(changes.contains(&Change::OnlyInConsul(property("extra", "x"))));
+        assert_eq!(changes.len(), 3);
+    }
+
+    #[test]
+    fn leaves_out_the_changes_the_ignore_file_covers() {
+        let ignore_file = env::temp_dir().join(format!("consul-help-{}.ignore", process::id()));
+        fs::write(
+            &ignore_file,
+            "secrets/**\nurl when https://(\\w+)\\.(?:dev|prod)\n",
+        )
+        .unwrap();
+        let mut opts = compare_opts(&[]);
+        opts.ignore_rules = ignore::load(Some(&ignore_file)).unwrap();
+        fs::remove_file(&ignore_file).unwrap();
+
+        let changes = compare_properties(
+            &[property("url", "https://api.dev"), property("name", "a")],
+            &[
+                property("url", "https://api.prod"),
+                property("name", "b"),
+                property("secrets/token", "x"),
+            ],
+            &opts,
+        );
+        assert_eq!(
+            changes,
+            [Change::Changed {
+                key: "name".to_string(),
+                file_value: "a".to_string(),
+                consul_value: "b".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn never_changes_keys_to_unresolved_placeholders() {
+        let file = [property("password", "${CONSUL_HELP_TEST_UNSET}")];
+        let consul = [property("password", "secret")];
+        assert_eq!(
+            compare_properties(&file, &consul, &compare_opts(&[])).len(),
+            1
+        );
+        assert!(
+            compare_properties(&file, &consul, &compare_opts(&["--placeholders", "report"]))
+                .is_empty()
+        );
+
+        let file = [property("port", "${CONSUL_HELP_TEST_UNSET:8080}")];
+        let consul = [property("port", "8080")];
+        assert!(compare_properties(
+            &file,
+            &consul,
+            &compare_opts(&["--placeholders", "resolve"])
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn empty_consul_keys_follow_the_empty_values_option() {
+        let file = [property("a", "1")];
+        let consul = [property("a", ""), property("folder/", "")];
+        assert_eq!(
+            compare_properties(&file, &consul, &compare_opts(&[])),
+            [
+                Change::Changed {
+                    key: "a".to_string(),
+                    file_value: "1".to_string(),
+                    consul_value: String::new(),
+                },
+                Change::OnlyInConsul(property("folder/", "")),
+            ]
+        );
+        assert!(compare_properties(
+            &[],
+            &consul[..1],
+            &compare_opts(&["--empty-values", "missing"])
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use consulrs::error::ClientError;
+use std::{fmt::Display, io, path::Path, process::ExitCode, time::Duration};
+use thiserror::Error;
+
+/// What a command found, mapped onto the process exit code.
+///
+/// The binary exits with:
+///
+/// * `0` when the command succeeded and found no differences
+/// * `1` when differences, conflicts or rule violations were found
+/// * `2` when the command line arguments are invalid
+/// * `3` when an input file could not be read or parsed
+/// * `4` when a Consul request failed
+/// * `5` when an output could not be written
+/// * `6` when the command ran past its --deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Clean,
+    Drift,
+}
+
+impl Outcome {
+    pub fn drift_if(found: bool) -> Outcome {
+        if found {
+            Outcome::Drift
+        } else {
+            Outcome::Clean
+        }
+    }
+}
+
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> ExitCode {
+        match outcome {
+            Outcome::Clean => ExitCode::SUCCESS,
+            Outcome::Drift => ExitCode::from(1),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}")]
+    Usage(String),
+    #[error("Unable to read {path}: {message}")]
+    Input { path: String, message: String },
+    #[error("{context}: {}", describe(.source))]
+    Consul {
+        context: String,
+        source: ClientError,
+    },
+    #[error("{0}")]
+    Rejected(String),
+    #[error("Unable to write {path}: {source}")]
+    Output { path: String, source: io::Error },
+    #[error("Unable to serialize output: {0}")]
+    Serialize(String),
+    #[error("Deadline of {0:?} exceeded, the command was stopped.")]
+    Deadline(Duration),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn read(path: &Path, source: impl Display) -> Error {
+        Error::Input {
+            path: path.display().to_string(),
+            message: source.to_string(),
+        }
+    }
+
+    pub fn write(path: &Path, source: io::Error) -> Error {
+        Error::Output {
+            path: path.display().to_string(),
+            source,
+        }
+    }
+
+    pub fn serialize(source: impl Display) -> Error {
+        Error::Serialize(source.to_string())
+    }
+
+    pub fn consul(context: impl Into<String>) -> impl FnOnce(ClientError) -> Error {
+        let context = context.into();
+        move |source| Error::Consul { context, source }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(match self {
+            Error::Usage(_) => 2,
+            Error::Input { .. } => 3,
+            Error::Consul { .. } | Error::Rejected(_) => 4,
+            Error::Output { .. } | Error::Serialize(_) => 5,
+            Error::Deadline(_) => 6,
+        })
+    }
+}
+
+fn describe(source: &ClientError) -> String {
+    match source {
+        ClientError::APIError {
+            code,
+            message: Some(message),
+        } if !message.is_empty() => format!("{} ({})", source, message.trim()),
+        // The innermost transport error names the cause, e.g. a refused connection
+        ClientError::RestClientError { source } => {
+            let mut cause: &dyn std::error::Error = source;
+            while let Some(error) = cause.source() {
+                cause = error;
+            }
+            format!("{}: {}", source, cause)
+        }
+        _ => source.to_string(),
+    }
+}
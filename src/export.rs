@@ -0,0 +1,87 @@
+use crate::{
+    error::{Error, Result},
+    properties::ConsulProperties,
+};
+
+pub mod ansible;
+pub mod env_file;
+pub mod kubernetes;
+
+/// The metadata of an exported Kubernetes manifest.
+#[derive(Debug)]
+pub struct Metadata<'a> {
+    pub name: String,
+    pub namespace: Option<&'a str>,
+    pub labels: &'a [(String, String)],
+}
+
+/// A name Kubernetes accepts for an object: lowercase letters, digits, dashes
+/// and dots, starting and ending with a letter or digit.
+pub fn object_name(text: &str) -> String {
+    let name: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '.' | '-' => c,
+            _ => '-',
+        })
+        .collect();
+    name.trim_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_string()
+}
+
+/// Whether a key is a folder key, or has no segments at all, and so holds no
+/// value to export.
+fn is_folder(item: &ConsulProperties) -> bool {
+    item.key.ends_with('/') || item.key.split('/').all(str::is_empty)
+}
+
+/// The keys of the properties as Kubernetes data keys, which may not hold
+/// slashes, so folders are joined with dots. Any other character Kubernetes
+/// refuses, such as the brackets of a list element, is an error.
+fn data_key(item: &ConsulProperties) -> Result<String> {
+    let key = item.key.replace('/', ".");
+    if key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Ok(key);
+    }
+    Err(Error::Usage(format!(
+        "{} cannot be exported as the data key `{}`, which may only hold letters, digits, `-`, `_` and `.`.",
+        item.key, key
+    )))
+}
+
+/// A variable name from the prefix and the segments of a key joined by the
+/// separator, with every character but ASCII letters, digits and underscores
+/// replaced by an underscore and an underscore added before a leading digit.
+/// A prefix that leaves the name invalid is an error.
+fn variable_name(prefix: &str, key: &str, separator: &str) -> Result<String> {
+    let name = prefix.to_string()
+        + &key
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                segment
+                    .chars()
+                    .map(|c| match c.is_ascii_alphanumeric() {
+                        true => c,
+                        false => '_',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(separator);
+    let name = match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(Error::Usage(format!(
+            "{} cannot be exported as the variable `{}`, which may only hold letters, digits and underscores.",
+            key, name
+        )));
+    }
+    Ok(name)
+}
@@ -0,0 +1,73 @@
+use serde_yaml::{
+    value::{Tag, TaggedValue},
+    Mapping, Value,
+};
+
+use super::{is_folder, variable_name};
+use crate::{
+    error::{Error, Result},
+    properties::ConsulProperties,
+};
+
+/// Renders the properties as an Ansible variables file for `group_vars`,
+/// with a lowercase variable per key named after its segments joined by
+/// underscores and starting with the prefix. Values that look like Jinja
+/// templates are tagged `!unsafe`, so Ansible keeps them as they are.
+pub fn render(properties: &[ConsulProperties], prefix: &str) -> Result<String> {
+    let variables: Mapping = properties
+        .iter()
+        .filter(|item| !is_folder(item))
+        .map(|item| {
+            let name = variable_name(prefix, &item.key, "_")?.to_lowercase();
+            let value = Value::from(item.value.clone());
+            let value = match ["{{", "{%", "{#"]
+                .iter()
+                .any(|start| item.value.contains(start))
+            {
+                true => Value::Tagged(Box::new(TaggedValue {
+                    tag: Tag::new("unsafe"),
+                    value,
+                })),
+                false => value,
+            };
+            Ok((Value::from(name), value))
+        })
+        .collect::<Result<_>>()?;
+    serde_yaml::to_string(&variables)
+        .map(|yaml| format!("---\n{}", yaml))
+        .map_err(Error::serialize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn names_variables_after_their_keys_and_the_prefix() {
+        let properties = [
+            property("", ""),
+            property("db/", ""),
+            property("DB/Url", "jdbc:h2:mem"),
+            property("greeting", "{{ name }}"),
+        ];
+        assert_eq!(
+            render(&properties, "app_").unwrap(),
+            "---\napp_db_url: jdbc:h2:mem\napp_greeting: !unsafe '{{ name }}'\n"
+        );
+    }
+
+    #[test]
+    fn rejects_a_prefix_that_is_not_a_variable_name() {
+        assert!(matches!(
+            render(&[property("db/url", "a")], "my-app_"),
+            Err(Error::Usage(_))
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use super::{is_folder, variable_name};
+use crate::{
+    error::{Error, Result},
+    properties::ConsulProperties,
+};
+
+/// Renders the properties as the `NAME=value` lines of a Docker env file,
+/// naming each variable after the uppercased segments of its key joined by
+/// the separator, such as `A_B_C` for `a/b/c`. Docker takes the rest of the
+/// line literally, so values are not quoted and cannot span lines.
+pub fn render(properties: &[ConsulProperties], separator: &str) -> Result<String> {
+    let mut text = String::new();
+    for item in properties.iter().filter(|item| !is_folder(item)) {
+        if item.value.contains(['\n', '\r']) {
+            return Err(Error::Rejected(format!(
+                "{} has a multi-line value, which an env file cannot hold.",
+                item.key
+            )));
+        }
+        let _ = writeln!(
+            text,
+            "{}={}",
+            variable_name("", &item.key, separator)?.to_ascii_uppercase(),
+            item.value
+        );
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn names_variables_after_their_keys() {
+        let properties = [
+            property("", ""),
+            property("db/", ""),
+            property("db/url", "jdbc:h2:mem"),
+            property("server/max-threads", "8"),
+            property("2fa/enabled", "true"),
+        ];
+        assert_eq!(
+            render(&properties, "_").unwrap(),
+            "DB_URL=jdbc:h2:mem\nSERVER_MAX_THREADS=8\n_2FA_ENABLED=true\n"
+        );
+        assert_eq!(
+            render(&properties[2..3], "__").unwrap(),
+            "DB__URL=jdbc:h2:mem\n"
+        );
+    }
+
+    #[test]
+    fn rejects_multi_line_values_and_invalid_names() {
+        assert!(render(&[property("motd", "a\nb")], "_").is_err());
+        assert!(matches!(
+            render(&[property("db/url", "a")], "-"),
+            Err(Error::Usage(_))
+        ));
+    }
+}
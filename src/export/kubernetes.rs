@@ -0,0 +1,133 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_yaml::{Mapping, Value};
+
+use super::{data_key, is_folder, Metadata};
+use crate::{
+    error::{Error, Result},
+    properties::ConsulProperties,
+};
+
+/// Renders the properties as the `data` of a ConfigMap manifest, with the
+/// folders of each key joined by dots.
+pub fn config_map(properties: &[ConsulProperties], metadata: &Metadata) -> Result<String> {
+    let data: Mapping = properties
+        .iter()
+        .filter(|item| !is_folder(item))
+        .map(|item| {
+            Ok((
+                Value::from(data_key(item)?),
+                Value::from(item.value.clone()),
+            ))
+        })
+        .collect::<Result<_>>()?;
+    manifest("ConfigMap", metadata, vec![("data", data.into())])
+}
+
+/// Renders the properties as an `Opaque` Secret manifest, with the values
+/// base64 encoded under `data`, or in plain text under `stringData` for the
+/// API server to encode.
+pub fn secret(
+    properties: &[ConsulProperties],
+    metadata: &Metadata,
+    string_data: bool,
+) -> Result<String> {
+    let data: Mapping = properties
+        .iter()
+        .filter(|item| !is_folder(item))
+        .map(|item| {
+            let value = match string_data {
+                true => item.value.clone(),
+                false => STANDARD.encode(&item.value),
+            };
+            Ok((Value::from(data_key(item)?), Value::from(value)))
+        })
+        .collect::<Result<_>>()?;
+
+    let field = if string_data { "stringData" } else { "data" };
+    manifest(
+        "Secret",
+        metadata,
+        vec![("type", "Opaque".into()), (field, data.into())],
+    )
+}
+
+/// Lays out a core Kubernetes object with its metadata and then the given
+/// fields.
+fn manifest(kind: &str, metadata: &Metadata, fields: Vec<(&str, Value)>) -> Result<String> {
+    let mut object_metadata = Mapping::new();
+    object_metadata.insert("name".into(), metadata.name.clone().into());
+    if let Some(namespace) = metadata.namespace {
+        object_metadata.insert("namespace".into(), namespace.into());
+    }
+    if !metadata.labels.is_empty() {
+        let labels: Mapping = metadata
+            .labels
+            .iter()
+            .map(|(key, value)| (Value::from(key.clone()), Value::from(value.clone())))
+            .collect();
+        object_metadata.insert("labels".into(), labels.into());
+    }
+
+    let mut object = Mapping::new();
+    object.insert("apiVersion".into(), "v1".into());
+    object.insert("kind".into(), kind.into());
+    object.insert("metadata".into(), object_metadata.into());
+    for (field, value) in fields {
+        object.insert(field.into(), value);
+    }
+    serde_yaml::to_string(&object).map_err(Error::serialize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn metadata() -> Metadata<'static> {
+        Metadata {
+            name: "app".to_string(),
+            namespace: Some("apps"),
+            labels: &[],
+        }
+    }
+
+    #[test]
+    fn joins_folders_with_dots_and_skips_folder_keys() {
+        let properties = [
+            property("", ""),
+            property("db/", ""),
+            property("db/url", "jdbc:h2:mem"),
+            property("name", "app"),
+        ];
+        assert_eq!(
+            config_map(&properties, &metadata()).unwrap(),
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: app\n  namespace: apps\ndata:\n  db.url: jdbc:h2:mem\n  name: app\n"
+        );
+    }
+
+    #[test]
+    fn encodes_secret_values_unless_string_data() {
+        let properties = [property("password", "hunter2")];
+        assert!(secret(&properties, &metadata(), false)
+            .unwrap()
+            .ends_with("type: Opaque\ndata:\n  password: aHVudGVyMg==\n"));
+        assert!(secret(&properties, &metadata(), true)
+            .unwrap()
+            .ends_with("type: Opaque\nstringData:\n  password: hunter2\n"));
+    }
+
+    #[test]
+    fn rejects_keys_kubernetes_refuses() {
+        let properties = [property("hosts[0]", "a")];
+        assert!(matches!(
+            config_map(&properties, &metadata()),
+            Err(Error::Usage(_))
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+use std::{path::Path, process::Command};
+
+use crate::error::{Error, Result};
+
+/// Returns the raw contents of a file as committed at a git revision, using
+/// `git show` from the directory the file lives in so the path can be given
+/// relative to the current directory or as an absolute path.
+pub fn show(file_path: &Path, git_ref: &str) -> Result<Vec<u8>> {
+    let directory = match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| Error::read(file_path, "not a file"))?
+        .to_string_lossy();
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .arg("show")
+        .arg(format!("{}:./{}", git_ref, file_name))
+        .output()
+        .map_err(|e| Error::read(file_path, format!("unable to run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::read(
+            file_path,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,139 @@
+use regex::Regex;
+use std::{fs, io::ErrorKind, path::Path};
+
+use crate::{
+    error::{Error, Result},
+    pattern,
+};
+
+/// The ignore file read from the working directory when --ignore-file is not
+/// given.
+pub const DEFAULT_FILE: &str = ".consulhelpignore";
+
+/// A line of an ignore file, matching the keys whose differences are
+/// expected, and optionally the values they are expected to differ in.
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    key: Regex,
+    value: Option<Regex>,
+}
+
+impl IgnoreRule {
+    /// Whether the rule covers a difference, given the value on each side
+    /// that has the key. A rule with a value regex only covers a key on both
+    /// sides whose values both match it with the same captures, so
+    /// `https://(\w+)\.(dev|prod)\.internal` ignores a change of environment
+    /// but not of service.
+    pub fn matches(&self, key: &str, file_value: Option<&str>, consul_value: Option<&str>) -> bool {
+        if !self.key.is_match(key) {
+            return false;
+        }
+        let Some(value) = &self.value else {
+            return true;
+        };
+        let (Some(file_value), Some(consul_value)) = (file_value, consul_value) else {
+            return false;
+        };
+        match (value.captures(file_value), value.captures(consul_value)) {
+            (Some(file_captures), Some(consul_captures)) => {
+                // The whole match is group 0, which is what differs
+                (1..value.captures_len()).all(|group| {
+                    file_captures.get(group).map(|capture| capture.as_str())
+                        == consul_captures.get(group).map(|capture| capture.as_str())
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Reads the rules of an ignore file, one key glob or `re:` regex per line,
+/// optionally followed by `when` and a regex both values must match, with
+/// equal captures. Blank lines and `#` comments are skipped. Without a file,
+/// the default one is read if it exists.
+pub fn load(ignore_file: Option<&Path>) -> Result<Vec<IgnoreRule>> {
+    let (file_path, required) = match ignore_file {
+        Some(ignore_file) => (ignore_file, true),
+        None => (Path::new(DEFAULT_FILE), false),
+    };
+    let contents = match fs::read_to_string(file_path) {
+        Err(e) if e.kind() == ErrorKind::NotFound && !required => return Ok(Vec::new()),
+        res => res.map_err(|e| Error::read(file_path, e))?,
+    };
+
+    let mut rules = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_error = |e: String| Error::read(file_path, format!("line {}: {}", number + 1, e));
+        let (key, value) = match line.split_once(" when ") {
+            Some((key, value)) => {
+                let value = Regex::new(value.trim()).map_err(|e| line_error(e.to_string()))?;
+                (key.trim(), Some(value))
+            }
+            None => (line, None),
+        };
+        let key = pattern::parse_key_pattern(key).map_err(line_error)?;
+        rules.push(IgnoreRule { key, value });
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        env, process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn rules(contents: &str) -> Result<Vec<IgnoreRule>> {
+        // Tests run in parallel, so each one writes a file of its own
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+        let ignore_file = env::temp_dir().join(format!(
+            "consul-help-{}-{}.ignore",
+            process::id(),
+            FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&ignore_file, contents).unwrap();
+        let rules = load(Some(&ignore_file));
+        fs::remove_file(&ignore_file).unwrap();
+        rules
+    }
+
+    #[test]
+    fn matches_keys_by_glob_or_regex() {
+        let rules = rules("# comment\n\nsecrets/**\nre:_at$\n").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].matches("secrets/db/password", Some("a"), None));
+        assert!(!rules[0].matches("db/secrets", Some("a"), Some("b")));
+        assert!(rules[1].matches("build/created_at", None, Some("1")));
+    }
+
+    #[test]
+    fn requires_an_ignore_file_only_when_given() {
+        let missing = env::temp_dir().join(format!("consul-help-{}-missing", process::id()));
+        assert!(load(Some(&missing)).is_err());
+        assert!(rules("re:(\n").is_err());
+    }
+
+    #[test]
+    fn value_rules_match_when_the_captures_are_equal() {
+        let rules = rules("url when https://(\\w+)\\.(?:dev|prod)\\.internal\n").unwrap();
+        let rule = &rules[0];
+        assert!(rule.matches(
+            "url",
+            Some("https://api.dev.internal"),
+            Some("https://api.prod.internal")
+        ));
+        assert!(!rule.matches(
+            "url",
+            Some("https://api.dev.internal"),
+            Some("https://web.prod.internal")
+        ));
+        assert!(!rule.matches("url", Some("https://api.dev.internal"), Some("other")));
+        assert!(!rule.matches("url", Some("https://api.dev.internal"), None));
+    }
+}
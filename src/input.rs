@@ -0,0 +1,617 @@
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::{Encoding, InputFormat, InputOpts},
+    error::{Error, Result},
+    git,
+    output::{progress, trace},
+    properties::{self, ConsulProperties},
+};
+
+mod config_map;
+mod consul_export;
+mod csv;
+mod documents;
+mod dotenv;
+mod duplicates;
+mod helm_set;
+mod ini;
+mod java_properties;
+mod schema;
+mod xml;
+mod yaml_lines;
+
+/// The input path that reads from stdin instead of a file.
+const STDIN: &str = "-";
+
+impl InputFormat {
+    /// Guesses the format from the file extension, falling back to YAML.
+    pub fn detect(file_path: &Path) -> InputFormat {
+        InputFormat::from_file_name(file_path).unwrap_or(InputFormat::Yaml)
+    }
+
+    /// The format a file name is recognized as, if any.
+    fn from_file_name(file_path: &Path) -> Option<InputFormat> {
+        match file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("yml") | Some("yaml") => Some(InputFormat::Yaml),
+            Some("json") => Some(InputFormat::Json),
+            Some("properties") => Some(InputFormat::Properties),
+            Some("toml") => Some(InputFormat::Toml),
+            Some("env") => Some(InputFormat::Dotenv),
+            Some("hcl") | Some("tf") | Some("nomad") => Some(InputFormat::Hcl),
+            Some("ini") => Some(InputFormat::Ini),
+            Some("xml") => Some(InputFormat::Xml),
+            Some("csv") => Some(InputFormat::Csv),
+            _ if is_dotenv(file_path) => Some(InputFormat::Dotenv),
+            _ => None,
+        }
+    }
+}
+
+/// Files named `.env` or `.env.<environment>` have no extension to go by.
+fn is_dotenv(file_path: &Path) -> bool {
+    file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == ".env" || name.starts_with(".env."))
+}
+
+/// Reads a single input file, or stdin when the path is `-`, with its keys
+/// joined by --key-separator.
+pub fn load(file_path: &PathBuf, input_opts: &InputOpts) -> Result<Vec<ConsulProperties>> {
+    let mut properties = read(file_path, input_opts)?;
+    join_segments(&mut properties, &input_opts.key_separator);
+    Ok(properties)
+}
+
+fn read(file_path: &PathBuf, input_opts: &InputOpts) -> Result<Vec<ConsulProperties>> {
+    if file_path.as_os_str() == STDIN {
+        progress!("Loading properties from stdin");
+
+        let stdin: &Path = "stdin".as_ref();
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::read(stdin, e))?;
+        let contents = decode(stdin, bytes, input_opts.encoding)?;
+        if contents.trim().is_empty() {
+            return Err(Error::read(stdin, "nothing was piped in"));
+        }
+        return parse(stdin, &contents, input_opts).map(|properties| traced(stdin, properties));
+    }
+
+    progress!("Loading properties from file: {}", file_path.display());
+
+    let bytes = fs::read(file_path).map_err(|e| Error::read(file_path, e))?;
+    let contents = decode(file_path, bytes, input_opts.encoding)?;
+    parse(file_path, &contents, input_opts).map(|properties| traced(file_path, properties))
+}
+
+#[cfg(unix)]
+fn stdin_is_pipe() -> bool {
+    use std::os::{fd::AsFd, unix::fs::FileTypeExt};
+
+    io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .and_then(|fd| fs::File::from(fd).metadata())
+        .is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn stdin_is_pipe() -> bool {
+    use std::io::IsTerminal;
+
+    !io::stdin().is_terminal()
+}
+
+/// Refuses input without any key, for the commands that would otherwise
+/// delete every key under the prefix.
+pub fn require_keys(file_paths: &[PathBuf], properties: &[ConsulProperties]) -> Result<()> {
+    if !properties.is_empty() {
+        return Ok(());
+    }
+    let names = match file_paths {
+        [] => "stdin".to_string(),
+        file_paths => file_paths
+            .iter()
+            .map(|file_path| file_path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    Err(Error::read(
+        Path::new(&names),
+        "no keys found, refusing to delete every key under the prefix",
+    ))
+}
+
+/// A file followed by the files for each active profile next to it, in the
+/// order of the profiles, such as `application-prod.yml` after
+/// `application.yml`, for those that exist.
+fn profile_files(file_path: &Path, profiles: &[String]) -> Vec<PathBuf> {
+    let mut file_paths = vec![file_path.to_path_buf()];
+    if file_path.as_os_str() == STDIN {
+        return file_paths;
+    }
+    let Some(stem) = file_path.file_stem().and_then(|stem| stem.to_str()) else {
+        return file_paths;
+    };
+    for profile in profiles {
+        let file_name = match file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some(extension) => format!("{}-{}.{}", stem, profile, extension),
+            None => format!("{}-{}", stem, profile),
+        };
+        let profile_path = file_path.with_file_name(file_name);
+        if profile_path.is_file() {
+            file_paths.push(profile_path);
+        }
+    }
+    file_paths
+}
+
+/// Traces each key parsed from an input file with -d.
+fn traced(file_path: &Path, properties: Vec<ConsulProperties>) -> Vec<ConsulProperties> {
+    for item in &properties {
+        trace!("Read {} from {}", item.key, file_path.display());
+    }
+    properties
+}
+
+/// Reads the input file as it was committed at a git revision instead of
+/// from the working tree.
+pub fn load_at(
+    file_path: &Path,
+    git_ref: &str,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    progress!(
+        "Loading properties from file: {} at revision {}",
+        file_path.display(),
+        git_ref
+    );
+
+    let contents = decode(
+        file_path,
+        git::show(file_path, git_ref)?,
+        input_opts.encoding,
+    )?;
+    parse(file_path, &contents, input_opts).map(|properties| traced(file_path, properties))
+}
+
+fn decode(file_path: &Path, bytes: Vec<u8>, encoding: Encoding) -> Result<String> {
+    match encoding {
+        Encoding::Utf8 => String::from_utf8(bytes).map_err(|e| Error::read(file_path, e)),
+        Encoding::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+    }
+}
+
+/// Parses a file held in a Consul key, in the format its name is recognized
+/// as regardless of --format, with its keys joined by --key-separator.
+pub fn parse_value(
+    name: &str,
+    contents: &str,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    let value_opts = InputOpts {
+        format: None,
+        ..input_opts.clone()
+    };
+    let mut properties = parse(Path::new(name), contents, &value_opts)?;
+    join_segments(&mut properties, &input_opts.key_separator);
+    Ok(properties)
+}
+
+/// Reads each input file, at a git revision if one is given, and layers them
+/// so the keys of later files override those of earlier ones, followed by
+/// the --set overrides and --key-separator. In the working tree, each file
+/// is followed by its profile specific siblings.
+pub fn load_layers(
+    file_paths: &[PathBuf],
+    git_ref: Option<&str>,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    // Without any input file the properties are expected on stdin, but only
+    // when they are piped in and not from a terminal, /dev/null or nothing
+    let stdin = [PathBuf::from(STDIN)];
+    let file_paths = match file_paths {
+        [] if !stdin_is_pipe() => {
+            return Err(Error::Usage(
+                "--input-property is required unless the properties are piped to stdin."
+                    .to_string(),
+            ))
+        }
+        [] => &stdin[..],
+        file_paths => file_paths,
+    };
+
+    // Spring reads the profile specific files next to each file after it
+    let file_paths: Vec<_> = match git_ref {
+        Some(_) => file_paths.to_vec(),
+        None => file_paths
+            .iter()
+            .flat_map(|file_path| profile_files(file_path, &input_opts.profile))
+            .collect(),
+    };
+
+    let mut merged: Vec<ConsulProperties> = Vec::new();
+
+    for file_path in &file_paths {
+        let layer = match git_ref {
+            Some(git_ref) => load_at(file_path, git_ref, input_opts)?,
+            None => read(file_path, input_opts)?,
+        };
+
+        // A list in a later file replaces the whole list, as it does in Spring,
+        // instead of only the elements at the same positions
+        let lists: HashSet<_> = layer
+            .iter()
+            .filter_map(|item| list_path(&item.key))
+            .collect();
+        merged.retain(|item| list_path(&item.key).is_none_or(|list| !lists.contains(list)));
+
+        let positions: HashMap<_, _> = merged
+            .iter()
+            .enumerate()
+            .map(|(position, item)| (item.key.clone(), position))
+            .collect();
+        for item in layer {
+            match positions.get(&item.key) {
+                Some(position) => merged[*position].value = item.value,
+                None => merged.push(item),
+            }
+        }
+    }
+
+    helm_set::apply(&mut merged, &input_opts.set).map_err(Error::Usage)?;
+    join_segments(&mut merged, &input_opts.key_separator);
+    Ok(merged)
+}
+
+/// Joins the path segments of the keys, which are read separated by `/`,
+/// with another separator.
+fn join_segments(properties: &mut [ConsulProperties], separator: &str) {
+    if separator == "/" {
+        return;
+    }
+    for item in properties {
+        item.key = item.key.replace('/', separator);
+    }
+}
+
+/// The path of the outermost list a key is an element of, such as `a/b` for
+/// `a/b[0]/c[1]`.
+fn list_path(key: &str) -> Option<&str> {
+    key.find('[').map(|position| &key[..position])
+}
+
+/// Where each input key is set, as the file and the line of the definition
+/// that wins once the files are layered.
+pub type Locations = HashMap<String, (PathBuf, usize)>;
+
+/// Finds the line each key of the input files is set on, for the formats
+/// with a key per line: block style YAML, properties, .env, INI and CSV.
+/// Inputs that cannot be read again, such as stdin, are skipped.
+pub fn locate(file_paths: &[PathBuf], input_opts: &InputOpts) -> Locations {
+    let mut locations = Locations::new();
+    for file_path in file_paths {
+        let Ok(bytes) = fs::read(file_path) else {
+            continue;
+        };
+        let Ok(contents) = decode(file_path, bytes, input_opts.encoding) else {
+            continue;
+        };
+
+        let line_of = |(key, line): (&str, usize)| (key.to_string(), line);
+        let lines: Vec<(String, usize)> = match input_opts
+            .format
+            .unwrap_or_else(|| InputFormat::detect(file_path))
+        {
+            InputFormat::Yaml => {
+                let mut lines = Vec::new();
+                yaml_lines::scan(&contents, |_, path, line| {
+                    lines.push((path.to_string(), line))
+                });
+                lines
+            }
+            InputFormat::Properties => java_properties::parse(&contents)
+                .lines()
+                .map(|(key, line)| (key.replace('.', "/"), line))
+                .collect(),
+            InputFormat::Dotenv => dotenv::parse(&contents)
+                .lines()
+                .map(|(name, line)| (dotenv::key_path(name, &input_opts.env_separator), line))
+                .collect(),
+            InputFormat::Ini => ini::parse(&contents)
+                .map(|pairs| pairs.lines().map(line_of).collect())
+                .unwrap_or_default(),
+            InputFormat::Csv => csv::parse(&contents, input_opts.delimiter)
+                .map(|pairs| pairs.lines().map(line_of).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        for (key, line) in lines {
+            let key = match input_opts.key_separator.as_str() {
+                "/" => key,
+                separator => key.replace('/', separator),
+            };
+            locations.insert(key, (file_path.clone(), line));
+        }
+    }
+    locations
+}
+
+/// Parses the contents of an input file, detecting the format from the file
+/// name unless --format is given.
+pub fn parse(
+    file_path: &Path,
+    contents: &str,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    let read_error = |e| Error::read(file_path, e);
+
+    let properties = match input_opts
+        .format
+        .unwrap_or_else(|| InputFormat::detect(file_path))
+    {
+        InputFormat::Yaml => {
+            let documents = parse_documents(file_path, contents)?;
+            parse_yaml_documents(file_path, documents, input_opts)?
+        }
+        InputFormat::ConfigMap => {
+            let mut properties = Vec::new();
+            for document in parse_documents(file_path, contents)? {
+                properties.extend(parse_config_map(file_path, &document, input_opts)?);
+            }
+            properties
+        }
+        InputFormat::Json => {
+            let json: serde_json::Value =
+                serde_json::from_str(contents).map_err(|e| read_error(e.to_string()))?;
+            if consul_export::is_export(&json) {
+                parse_consul_export(json, input_opts).map_err(read_error)?
+            } else {
+                check_schema(file_path, &json, input_opts)?;
+                // JSON is converted to a YAML value so both share the same flattening
+                let yaml = serde_yaml::to_value(json).map_err(|e| read_error(e.to_string()))?;
+                properties::flatten_value(&yaml, input_opts)
+            }
+        }
+        InputFormat::ConsulExport => {
+            let json = serde_json::from_str(contents).map_err(|e| read_error(e.to_string()))?;
+            parse_consul_export(json, input_opts).map_err(read_error)?
+        }
+        InputFormat::Toml => {
+            let toml: toml::Value =
+                toml::from_str(contents).map_err(|e| read_error(e.to_string()))?;
+            properties::flatten_value(&toml_to_yaml(toml), input_opts)
+        }
+        // Blocks become nested mappings keyed by their type and then each label
+        InputFormat::Hcl => {
+            let hcl: hcl::Value = hcl::from_str(contents).map_err(|e| read_error(e.to_string()))?;
+            let yaml = serde_yaml::to_value(hcl).map_err(|e| read_error(e.to_string()))?;
+            properties::flatten_value(&yaml, input_opts)
+        }
+        InputFormat::Ini => check_duplicates(
+            file_path,
+            ini::parse(contents).map_err(read_error)?,
+            input_opts,
+        )?
+        .into_iter()
+        .map(|(key, value)| ConsulProperties { key, value })
+        .collect(),
+        InputFormat::Xml => xml::parse(contents)
+            .map_err(read_error)?
+            .into_iter()
+            .map(|(key, value)| ConsulProperties { key, value })
+            .collect(),
+        InputFormat::Csv => check_duplicates(
+            file_path,
+            csv::parse(contents, input_opts.delimiter).map_err(read_error)?,
+            input_opts,
+        )?
+        .into_iter()
+        .map(|(key, value)| ConsulProperties { key, value })
+        .collect(),
+        // Dotted keys are the flat form of nested YAML keys
+        InputFormat::Properties => {
+            check_duplicates(file_path, java_properties::parse(contents), input_opts)?
+                .into_iter()
+                .map(|(key, value)| ConsulProperties {
+                    key: key.replace('.', "/"),
+                    value,
+                })
+                .collect()
+        }
+        InputFormat::Dotenv => check_duplicates(file_path, dotenv::parse(contents), input_opts)?
+            .into_iter()
+            .map(|(name, value)| ConsulProperties {
+                key: dotenv::key_path(&name, &input_opts.env_separator),
+                value,
+            })
+            .collect(),
+    };
+    Ok(properties)
+}
+
+/// Parses a YAML stream, pointing out the lines of any duplicate keys the
+/// parser refuses.
+fn parse_documents(file_path: &Path, contents: &str) -> Result<Vec<Value>> {
+    documents::parse(contents).map_err(|e| {
+        for duplicate in duplicates::find_in_yaml(contents) {
+            report_duplicate(file_path, &duplicate);
+        }
+        Error::read(file_path, e)
+    })
+}
+
+/// Validates a YAML or JSON file against the --schema, reporting each
+/// violation before failing the run.
+fn check_schema(file_path: &Path, json: &serde_json::Value, input_opts: &InputOpts) -> Result<()> {
+    let Some(schema_path) = &input_opts.schema else {
+        return Ok(());
+    };
+
+    let contents = fs::read_to_string(schema_path).map_err(|e| Error::read(schema_path, e))?;
+    // JSON is valid YAML, so a schema may be written in either
+    let schema = serde_yaml::from_str(&contents).map_err(|e| Error::read(schema_path, e))?;
+    let violations = schema::violations(&schema, json).map_err(|e| Error::read(schema_path, e))?;
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+    violations
+        .iter()
+        .for_each(|violation| eprintln!("{}: {}", file_path.display(), violation));
+    Err(Error::read(
+        file_path,
+        format!("{} schema violations found", violations.len()),
+    ))
+}
+
+/// Reports the keys a line based file sets more than once, which keep their
+/// last value unless --strict makes them fail the run.
+fn check_duplicates(
+    file_path: &Path,
+    pairs: duplicates::Pairs,
+    input_opts: &InputOpts,
+) -> Result<Vec<(String, String)>> {
+    pairs
+        .duplicates
+        .iter()
+        .for_each(|duplicate| report_duplicate(file_path, duplicate));
+
+    match pairs.duplicates.len() {
+        count if count > 0 && input_opts.strict => Err(Error::read(
+            file_path,
+            format!("{} duplicate keys found", count),
+        )),
+        _ => Ok(pairs.into_pairs()),
+    }
+}
+
+fn report_duplicate(file_path: &Path, duplicate: &duplicates::Duplicate) {
+    eprintln!(
+        "{}:{}: duplicate key {}, first set on line {}",
+        file_path.display(),
+        duplicate.line,
+        duplicate.key,
+        duplicate.first_line
+    );
+}
+
+/// Merges the documents of a YAML stream that apply to the active profiles,
+/// in order, while ConfigMap documents are read by their entries.
+fn parse_yaml_documents(
+    file_path: &Path,
+    documents: Vec<Value>,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    let mut merged = Value::Null;
+    let mut config_maps = Vec::new();
+
+    for mut document in documents {
+        if config_map::is_config_map(&document) {
+            config_maps.extend(parse_config_map(file_path, &document, input_opts)?);
+        } else if documents::is_active(&document, &input_opts.profile) {
+            documents::remove_selector(&mut document);
+            documents::merge(&mut merged, document);
+        }
+    }
+
+    // A stream with no applicable documents has nothing to flatten
+    let mut properties = match merged {
+        Value::Null => Vec::new(),
+        merged => {
+            let json = serde_json::to_value(&merged).map_err(|e| Error::read(file_path, e))?;
+            check_schema(file_path, &json, input_opts)?;
+            properties::flatten_value(&merged, input_opts)
+        }
+    };
+    properties.extend(config_maps);
+    Ok(properties)
+}
+
+/// Compares each ConfigMap entry as a key, or with --parse-embedded, entries
+/// named like a config file such as `application.yml` by their contents.
+fn parse_config_map(
+    file_path: &Path,
+    yaml: &Value,
+    input_opts: &InputOpts,
+) -> Result<Vec<ConsulProperties>> {
+    let embedded_opts = InputOpts {
+        format: None,
+        ..input_opts.clone()
+    };
+
+    let mut properties = Vec::new();
+    for (key, value) in config_map::entries(yaml).map_err(|e| Error::read(file_path, e))? {
+        let embedded = Path::new(&key);
+        if input_opts.parse_embedded && InputFormat::from_file_name(embedded).is_some() {
+            properties.extend(parse(&file_path.join(embedded), &value, &embedded_opts)?);
+        } else {
+            properties.push(ConsulProperties { key, value });
+        }
+    }
+    Ok(properties)
+}
+
+/// Decodes a `consul kv export` and strips the prefix it was taken from, so
+/// its keys line up with the ones read under an app prefix.
+fn parse_consul_export(
+    json: serde_json::Value,
+    input_opts: &InputOpts,
+) -> std::result::Result<Vec<ConsulProperties>, String> {
+    let entries = consul_export::parse(json)?;
+    let prefix = match &input_opts.export_prefix {
+        Some(prefix) => format!("{}/", prefix.trim_end_matches('/')),
+        None => consul_export::common_folder(&entries),
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| {
+            // The folder key of the prefix itself has nothing left to compare
+            let key = key.strip_prefix(&prefix).filter(|key| !key.is_empty())?;
+            Some(ConsulProperties {
+                key: key.to_string(),
+                value,
+            })
+        })
+        .collect())
+}
+
+/// Converts a TOML value by hand, since serializing a TOML datetime through
+/// serde produces a private wrapper table instead of its text.
+fn toml_to_yaml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(value) => Value::String(value),
+        toml::Value::Integer(value) => Value::Number(value.into()),
+        toml::Value::Float(value) => Value::Number(value.into()),
+        toml::Value::Boolean(value) => Value::Bool(value),
+        toml::Value::Datetime(value) => Value::String(value.to_string()),
+        toml::Value::Array(values) => {
+            Value::Sequence(values.into_iter().map(toml_to_yaml).collect())
+        }
+        toml::Value::Table(table) => Value::Mapping(
+            table
+                .into_iter()
+                .map(|(key, value)| (Value::String(key), toml_to_yaml(value)))
+                .collect::<Mapping>(),
+        ),
+    }
+}
@@ -0,0 +1,34 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_yaml::Value;
+
+use crate::properties::value_to_string;
+
+/// Whether a YAML document is a Kubernetes ConfigMap manifest.
+pub fn is_config_map(yaml: &Value) -> bool {
+    yaml.get("apiVersion").is_some()
+        && yaml.get("kind").and_then(Value::as_str) == Some("ConfigMap")
+}
+
+/// Reads the `data` and `binaryData` entries of a ConfigMap, decoding the
+/// base64 of the binary ones.
+pub fn entries(yaml: &Value) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+
+    if let Some(data) = yaml.get("data").and_then(Value::as_mapping) {
+        for (key, value) in data {
+            entries.push((value_to_string(key), value_to_string(value)));
+        }
+    }
+    if let Some(binary_data) = yaml.get("binaryData").and_then(Value::as_mapping) {
+        for (key, value) in binary_data {
+            let key = value_to_string(key);
+            let bytes = STANDARD
+                .decode(value_to_string(value))
+                .map_err(|e| format!("binaryData {}: {}", key, e))?;
+            let value =
+                String::from_utf8(bytes).map_err(|e| format!("binaryData {}: {}", key, e))?;
+            entries.push((key, value));
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,64 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct ExportEntry {
+    key: String,
+    /// Base64 encoded, and null for folder keys
+    value: Option<String>,
+}
+
+/// Whether a JSON document has the shape written by `consul kv export`: an
+/// array of objects with `key`, `flags` and `value` fields.
+pub fn is_export(json: &serde_json::Value) -> bool {
+    json.as_array().is_some_and(|entries| {
+        !entries.is_empty()
+            && entries.iter().all(|entry| {
+                entry.get("key").is_some_and(serde_json::Value::is_string)
+                    && entry.get("flags").is_some()
+                    && entry.get("value").is_some()
+            })
+    })
+}
+
+/// Decodes the entries of an export into full keys and their values.
+pub fn parse(json: serde_json::Value) -> Result<Vec<(String, String)>, String> {
+    let entries: Vec<ExportEntry> = serde_json::from_value(json).map_err(|e| e.to_string())?;
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let value = match entry.value {
+                Some(value) => {
+                    let bytes = STANDARD
+                        .decode(value)
+                        .map_err(|e| format!("{}: {}", entry.key, e))?;
+                    String::from_utf8(bytes).map_err(|e| format!("{}: {}", entry.key, e))?
+                }
+                None => String::new(),
+            };
+            Ok((entry.key, value))
+        })
+        .collect()
+}
+
+/// The deepest folder, ending in a slash, that every key is under.
+pub fn common_folder(entries: &[(String, String)]) -> String {
+    let Some((first, _)) = entries.first() else {
+        return String::new();
+    };
+
+    let mut folder = match first.rfind('/') {
+        Some(position) => &first[..=position],
+        None => "",
+    };
+    for (key, _) in entries {
+        while !key.starts_with(folder) {
+            folder = match folder[..folder.len() - 1].rfind('/') {
+                Some(position) => &folder[..=position],
+                None => "",
+            };
+        }
+    }
+    folder.to_string()
+}
@@ -0,0 +1,72 @@
+use super::duplicates::Pairs;
+
+/// Parses a two column `key,value` CSV into key value pairs in file order,
+/// skipping a `key,value` header row if there is one. A key that appears
+/// more than once keeps its first position and its last value.
+pub fn parse(contents: &str, delimiter: u8) -> Result<Pairs, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+
+    let mut pairs = Pairs::default();
+    for (number, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        if record.len() == 1 && record[0].trim().is_empty() {
+            continue;
+        }
+        let line = record
+            .position()
+            .map_or(number as u64 + 1, |position| position.line());
+        if record.len() != 2 {
+            return Err(format!(
+                "line {}: expected 2 columns but found {}",
+                line,
+                record.len()
+            ));
+        }
+        if number == 0
+            && record[0].eq_ignore_ascii_case("key")
+            && record[1].eq_ignore_ascii_case("value")
+        {
+            continue;
+        }
+        pairs.insert(line as usize, record[0].to_string(), record[1].to_string());
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_the_header_row_and_blank_lines() {
+        let pairs = parse("key,value\ndb/url,\"jdbc:h2:mem,x\"\n\nname,app\n", b',').unwrap();
+        assert_eq!(
+            pairs.into_pairs(),
+            [
+                ("db/url".to_string(), "jdbc:h2:mem,x".to_string()),
+                ("name".to_string(), "app".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_on_the_given_delimiter() {
+        let pairs = parse("name;app\n", b';').unwrap();
+        assert_eq!(
+            pairs.into_pairs(),
+            [("name".to_string(), "app".to_string())]
+        );
+    }
+
+    #[test]
+    fn rejects_rows_without_two_columns() {
+        assert_eq!(
+            parse("name,app\nport\n", b',').unwrap_err(),
+            "line 2: expected 2 columns but found 1"
+        );
+    }
+}
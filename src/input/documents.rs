@@ -0,0 +1,346 @@
+use serde::Deserialize;
+use serde_yaml::{Deserializer, Mapping, Value};
+
+/// Parses every document of a YAML stream separated by `---`, skipping the
+/// empty ones and resolving merge keys. Aliases are already expanded by the
+/// parser.
+pub fn parse(contents: &str) -> Result<Vec<Value>, serde_yaml::Error> {
+    let mut documents = Vec::new();
+    for document in Deserializer::from_str(contents) {
+        let mut value = Value::deserialize(document)?;
+        if !value.is_null() {
+            apply_merge_keys(&mut value)
+                .map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// Resolves `<<` merge keys, where the keys of the mapping itself win over
+/// merged ones and earlier mappings in a merge list win over later ones.
+/// Unlike `Value::apply_merge` this also resolves merged mappings that have
+/// a merge key of their own.
+fn apply_merge_keys(value: &mut Value) -> Result<(), &'static str> {
+    match value {
+        Value::Mapping(mapping) => {
+            merge_into(mapping)?;
+            mapping.values_mut().try_for_each(apply_merge_keys)
+        }
+        Value::Sequence(sequence) => sequence.iter_mut().try_for_each(apply_merge_keys),
+        Value::Tagged(tagged) => apply_merge_keys(&mut tagged.value),
+        _ => Ok(()),
+    }
+}
+
+fn merge_into(mapping: &mut Mapping) -> Result<(), &'static str> {
+    let sources = match mapping.remove("<<") {
+        None => return Ok(()),
+        Some(Value::Mapping(source)) => vec![source],
+        Some(Value::Sequence(sources)) => sources
+            .into_iter()
+            .map(|source| match source {
+                Value::Mapping(source) => Ok(source),
+                _ => Err("a merge key list may only contain mappings"),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("a merge key must be a mapping or a list of mappings"),
+    };
+
+    for mut source in sources {
+        // Resolving the merged mapping first ranks its own merges with it
+        merge_into(&mut source)?;
+        for (key, value) in source {
+            mapping.entry(key).or_insert(value);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a document applies to the active profiles. Documents without a
+/// `spring.config.activate.on-profile` or legacy `spring.profiles` selector
+/// always apply. With no active profiles, Spring activates the `default`
+/// profile, so only the selectors matching it apply.
+pub fn is_active(document: &Value, profiles: &[String]) -> bool {
+    let Some(expressions) = selector(document) else {
+        return true;
+    };
+    let default_profile = [String::from("default")];
+    let profiles = match profiles {
+        [] => &default_profile[..],
+        profiles => profiles,
+    };
+
+    expressions.into_iter().any(|expression| {
+        let mut parser = ProfileExpression {
+            rest: expression,
+            profiles,
+        };
+        parser.any() && parser.rest.trim().is_empty()
+    })
+}
+
+/// Evaluates a profile expression against the active profiles, as Spring
+/// does: `!` negates, `&` and `|` combine and parentheses group, so
+/// `prod & (eu | us)` matches with the `prod` and `us` profiles active.
+struct ProfileExpression<'a> {
+    rest: &'a str,
+    profiles: &'a [String],
+}
+
+impl ProfileExpression<'_> {
+    fn any(&mut self) -> bool {
+        let mut matched = self.all();
+        while self.eat('|') {
+            matched |= self.all();
+        }
+        matched
+    }
+
+    fn all(&mut self) -> bool {
+        let mut matched = self.term();
+        while self.eat('&') {
+            matched &= self.term();
+        }
+        matched
+    }
+
+    fn term(&mut self) -> bool {
+        if self.eat('!') {
+            return !self.term();
+        }
+        if self.eat('(') {
+            let matched = self.any();
+            return self.eat(')') && matched;
+        }
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(['!', '&', '|', '(', ')', ' '])
+            .unwrap_or(self.rest.len());
+        let (profile, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        !profile.is_empty() && self.profiles.iter().any(|active| active == profile)
+    }
+
+    fn eat(&mut self, token: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The profile expressions of a document's selector, any of which may
+/// match. The selector is either a single string, where commas separate the
+/// expressions, or a list of them.
+fn selector(document: &Value) -> Option<Vec<&str>> {
+    let spring = document.get("spring")?;
+    let selector = spring
+        .get("config")
+        .and_then(|config| config.get("activate"))
+        .and_then(|activate| activate.get("on-profile"))
+        .or_else(|| {
+            spring
+                .get("profiles")
+                .filter(|profiles| is_selector(profiles))
+        })?;
+
+    let entries = match selector {
+        Value::Sequence(entries) => entries.iter().filter_map(Value::as_str).collect(),
+        selector => vec![selector.as_str()?],
+    };
+    Some(
+        entries
+            .into_iter()
+            .flat_map(|entry| entry.split(','))
+            .map(str::trim)
+            .filter(|expression| !expression.is_empty())
+            .collect(),
+    )
+}
+
+/// Whether a legacy `spring.profiles` is a selector, rather than a mapping
+/// such as `spring.profiles.active`.
+fn is_selector(profiles: &Value) -> bool {
+    profiles.is_string() || profiles.is_sequence()
+}
+
+/// Removes the profile selector, which describes the document rather than
+/// being part of the configuration.
+pub fn remove_selector(document: &mut Value) {
+    let Some(document) = document.as_mapping_mut() else {
+        return;
+    };
+    let legacy = document
+        .get("spring")
+        .and_then(|spring| spring.get("profiles"))
+        .is_some_and(is_selector);
+    if legacy {
+        remove_path(document, &["spring", "profiles"]);
+    }
+    remove_path(document, &["spring", "config", "activate", "on-profile"]);
+}
+
+/// Removes the value at a path of keys, and then the mappings on the path
+/// that removing it left empty. Whether there was a value to remove.
+fn remove_path(mapping: &mut Mapping, path: &[&str]) -> bool {
+    let Some((key, rest)) = path.split_first() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return mapping.remove(*key).is_some();
+    }
+    let Some(inner) = mapping.get_mut(*key).and_then(Value::as_mapping_mut) else {
+        return false;
+    };
+    let removed = remove_path(inner, rest);
+    if removed && inner.is_empty() {
+        mapping.remove(*key);
+    }
+    removed
+}
+
+/// Deep merges a later document into an earlier one, where mappings are
+/// merged key by key and any other value, including a list, replaces the
+/// earlier one.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(contents: &str) -> Value {
+        serde_yaml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn skips_empty_documents() {
+        let documents = parse("a: 1\n---\n---\nb: 2\n").unwrap();
+        assert_eq!(documents, [yaml("a: 1"), yaml("b: 2")]);
+    }
+
+    #[test]
+    fn resolves_merge_keys_with_own_keys_winning() {
+        let contents = "\
+base: &base
+  host: a
+  port: 1
+other: &other
+  <<: *base
+  port: 2
+  user: x
+app:
+  <<: [*other, *base]
+  host: b
+";
+        let documents = parse(contents).unwrap();
+        assert_eq!(documents[0]["app"], yaml("{host: b, port: 2, user: x}"));
+    }
+
+    #[test]
+    fn rejects_a_merge_key_that_is_not_a_mapping() {
+        assert!(parse("app:\n  <<: 1\n").is_err());
+        assert!(parse("app:\n  <<: [1]\n").is_err());
+    }
+
+    #[test]
+    fn selects_documents_by_profile() {
+        let profiles = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let document = |selector: &str| {
+            yaml(&format!(
+                "spring: {{config: {{activate: {{on-profile: '{}'}}}}}}",
+                selector
+            ))
+        };
+
+        assert!(is_active(&yaml("a: 1"), &[]));
+        assert!(!is_active(&document("prod"), &[]));
+        assert!(is_active(&document("default"), &[]));
+        assert!(is_active(&document("prod"), &profiles(&["prod"])));
+        assert!(is_active(&document("dev, prod"), &profiles(&["prod"])));
+        assert!(is_active(
+            &document("prod & (eu | us)"),
+            &profiles(&["prod", "us"])
+        ));
+        assert!(!is_active(
+            &document("prod & !us"),
+            &profiles(&["prod", "us"])
+        ));
+        assert!(is_active(
+            &yaml("spring: {profiles: prod}"),
+            &profiles(&["prod"])
+        ));
+    }
+
+    #[test]
+    fn selects_documents_by_a_list_of_profiles() {
+        let profiles = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let document = yaml("spring: {config: {activate: {on-profile: [dev, qa]}}}");
+        assert!(is_active(&document, &profiles(&["qa"])));
+        assert!(!is_active(&document, &profiles(&["prod"])));
+        assert!(!is_active(&document, &[]));
+        assert!(!is_active(
+            &yaml("spring: {profiles: [dev, qa]}"),
+            &profiles(&["prod"])
+        ));
+        assert!(is_active(
+            &yaml("spring: {profiles: {active: prod}}"),
+            &profiles(&["dev"])
+        ));
+    }
+
+    #[test]
+    fn removes_only_the_selector_and_the_mappings_it_leaves_empty() {
+        let mut document = yaml("spring: {config: {activate: {on-profile: [dev]}}}\nname: app");
+        remove_selector(&mut document);
+        assert_eq!(document, yaml("name: app"));
+
+        let mut document =
+            yaml("spring: {profiles: dev, config: {import: x, activate: {}}, cloud: {}}");
+        remove_selector(&mut document);
+        assert_eq!(
+            document,
+            yaml("spring: {config: {import: x, activate: {}}, cloud: {}}")
+        );
+
+        let mut document = yaml("spring: {profiles: {active: prod}}");
+        remove_selector(&mut document);
+        assert_eq!(document, yaml("spring: {profiles: {active: prod}}"));
+    }
+
+    #[test]
+    fn merges_mappings_and_replaces_other_values() {
+        let mut base = yaml("{db: {url: a, user: x}, hosts: [a, b]}");
+        merge(&mut base, yaml("{db: {url: b}, hosts: [c]}"));
+        assert_eq!(base, yaml("{db: {url: b, user: x}, hosts: [c]}"));
+    }
+}
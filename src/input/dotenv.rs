@@ -0,0 +1,116 @@
+use super::duplicates::Pairs;
+
+/// Parses the contents of a dotenv file into variable names and values in
+/// file order. Lines may start with `export`, values may be single quoted
+/// (taken literally), double quoted (with escapes, possibly spanning lines)
+/// or bare, in which case a ` #` starts a comment.
+pub fn parse(contents: &str) -> Pairs {
+    let mut pairs = Pairs::default();
+    let mut lines = contents.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim_start();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let mut quoted = quoted.to_string();
+            while !has_closing_quote(&quoted) {
+                let Some((_, next)) = lines.next() else {
+                    break;
+                };
+                quoted.push('\n');
+                quoted.push_str(next);
+            }
+            unescape_double_quoted(&quoted)
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            quoted.split('\'').next().unwrap_or_default().to_string()
+        } else {
+            match value.find(" #") {
+                Some(position) => value[..position].trim_end().to_string(),
+                None => value.trim_end().to_string(),
+            }
+        };
+
+        pairs.insert(number + 1, name.trim().to_string(), value);
+    }
+    pairs
+}
+
+/// Maps a variable name such as `DB_URL` to the key path `db/url`.
+pub fn key_path(name: &str, separator: &str) -> String {
+    name.split(separator)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn has_closing_quote(text: &str) -> bool {
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Takes the text up to the closing quote, resolving the escapes dotenv
+/// supports inside double quotes.
+fn unescape_double_quoted(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => {}
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bare_and_quoted_values() {
+        let contents = "# comment\nexport DB_URL=jdbc:h2:mem # comment\nNAME='a \\n b'\nMOTD=\"line\\tone\nline two\"\nEMPTY=\n";
+        assert_eq!(
+            parse(contents).into_pairs(),
+            [
+                ("DB_URL".to_string(), "jdbc:h2:mem".to_string()),
+                ("NAME".to_string(), "a \\n b".to_string()),
+                ("MOTD".to_string(), "line\tone\nline two".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_variable_names_to_key_paths() {
+        assert_eq!(key_path("DB_URL", "_"), "db/url");
+        assert_eq!(
+            key_path("SPRING__DATASOURCE__URL", "__"),
+            "spring/datasource/url"
+        );
+        assert_eq!(key_path("_DB__URL_", "_"), "db/url");
+    }
+}
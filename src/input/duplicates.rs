@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use super::yaml_lines;
+
+/// A key set more than once in an input file, with the lines of its first
+/// and repeated definitions counted from 1.
+#[derive(Debug)]
+pub struct Duplicate {
+    pub key: String,
+    pub first_line: usize,
+    pub line: usize,
+}
+
+/// Key value pairs in file order, where a key that appears more than once
+/// keeps its first position and its last value and is recorded as a
+/// duplicate.
+#[derive(Debug, Default)]
+pub struct Pairs {
+    pairs: Vec<(String, String)>,
+    /// The line each pair was last set on
+    lines: Vec<usize>,
+    positions: HashMap<String, (usize, usize)>,
+    pub duplicates: Vec<Duplicate>,
+}
+
+impl Pairs {
+    pub fn insert(&mut self, line: usize, key: String, value: String) {
+        match self.positions.get(&key) {
+            Some(&(position, first_line)) => {
+                self.pairs[position].1 = value;
+                self.lines[position] = line;
+                self.duplicates.push(Duplicate {
+                    key,
+                    first_line,
+                    line,
+                });
+            }
+            None => {
+                self.positions.insert(key.clone(), (self.pairs.len(), line));
+                self.pairs.push((key, value));
+                self.lines.push(line);
+            }
+        }
+    }
+
+    /// Each key with the line its value was last set on.
+    pub fn lines(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.pairs
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .zip(self.lines.iter().copied())
+    }
+
+    pub fn into_pairs(self) -> Vec<(String, String)> {
+        self.pairs
+    }
+}
+
+/// Finds the keys repeated within a mapping of block style YAML, by their
+/// indentation. Flow style mappings are not looked into, so this only
+/// locates the duplicates the YAML parser already refused.
+pub fn find_in_yaml(contents: &str) -> Vec<Duplicate> {
+    let mut duplicates = Vec::new();
+    let mut first_lines: HashMap<String, usize> = HashMap::new();
+    let mut current_document = 0;
+
+    yaml_lines::scan(contents, |document, path, line| {
+        if document != current_document {
+            first_lines.clear();
+            current_document = document;
+        }
+        match first_lines.get(path) {
+            Some(&first_line) => duplicates.push(Duplicate {
+                key: path.to_string(),
+                first_line,
+                line,
+            }),
+            None => {
+                first_lines.insert(path.to_string(), line);
+            }
+        }
+    });
+    duplicates
+}
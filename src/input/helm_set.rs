@@ -0,0 +1,148 @@
+use crate::properties::ConsulProperties;
+
+/// Applies Helm style `--set` overrides such as `image.tag=1.2,replicas=3`
+/// to flattened properties. Dots separate path segments, a backslash escapes
+/// a `.`, `,` or `=`, and a value of `null` removes the key, as it does in
+/// Helm. Setting a key replaces everything below it.
+pub fn apply(properties: &mut Vec<ConsulProperties>, overrides: &[String]) -> Result<(), String> {
+    for expression in overrides {
+        for assignment in split_unescaped(expression, ',') {
+            // Only the first `=` separates, so values may contain more
+            let Some(position) = find_unescaped(&assignment, '=') else {
+                return Err(format!(
+                    "invalid --set `{}`, expected key=value",
+                    assignment
+                ));
+            };
+            let (path, value) = (&assignment[..position], &assignment[position + 1..]);
+
+            let key = split_unescaped(path, '.')
+                .iter()
+                .map(|segment| unescape(segment))
+                .collect::<Vec<_>>()
+                .join("/");
+            properties.retain(|item| !is_at_or_below(&item.key, &key));
+
+            if value != "null" {
+                properties.push(ConsulProperties {
+                    key,
+                    value: unescape(value),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_at_or_below(key: &str, path: &str) -> bool {
+    match key.strip_prefix(path) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['/', '[']),
+        None => false,
+    }
+}
+
+/// Splits on a separator that is not escaped, keeping the escapes in the
+/// parts so they can be split again on another separator.
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while let Some(position) = find_unescaped(&text[start..], separator) {
+        parts.push(text[start..start + position].to_string());
+        start += position + separator.len_utf8();
+    }
+    parts.push(text[start..].to_string());
+    parts
+}
+
+fn find_unescaped(text: &str, separator: char) -> Option<usize> {
+    let mut escaped = false;
+    for (position, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => return Some(position),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn sets_and_replaces_keys() {
+        let mut properties = vec![property("image/tag", "1.0"), property("replicas", "1")];
+        apply(
+            &mut properties,
+            &[
+                "image.tag=1.2,replicas=3".to_string(),
+                "url=a=b".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            properties,
+            [
+                property("image/tag", "1.2"),
+                property("replicas", "3"),
+                property("url", "a=b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn replaces_everything_below_a_key() {
+        let mut properties = vec![
+            property("hosts[0]", "a"),
+            property("hosts[1]", "b"),
+            property("hosts/name", "c"),
+            property("hostsname", "d"),
+        ];
+        apply(&mut properties, &["hosts=e".to_string()]).unwrap();
+        assert_eq!(
+            properties,
+            [property("hostsname", "d"), property("hosts", "e")]
+        );
+    }
+
+    #[test]
+    fn removes_null_keys_and_unescapes_separators() {
+        let mut properties = vec![property("a", "1")];
+        apply(
+            &mut properties,
+            &[
+                "a=null".to_string(),
+                "labels.app\\.kubernetes\\.io=x\\,y".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(properties, [property("labels/app.kubernetes.io", "x,y")]);
+    }
+
+    #[test]
+    fn rejects_an_assignment_without_a_value() {
+        let mut properties = Vec::new();
+        assert!(apply(&mut properties, &["a=1,b".to_string()]).is_err());
+    }
+}
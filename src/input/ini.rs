@@ -0,0 +1,88 @@
+use super::duplicates::Pairs;
+
+/// Parses the contents of an INI file into key paths and values in file
+/// order. Keys inside a `[section]` are placed under it, keys before the
+/// first section stay at the top level, and lines starting with `;` or `#`
+/// are comments. A key that appears more than once keeps its last value.
+pub fn parse(contents: &str) -> Result<Pairs, String> {
+    let mut pairs = Pairs::default();
+    let mut section: Option<String> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| format!("line {}: unterminated section header", number + 1))?;
+            section = Some(name.trim().to_string());
+            continue;
+        }
+
+        let Some(position) = line.find(['=', ':']) else {
+            return Err(format!("line {}: expected key = value", number + 1));
+        };
+        let name = line[..position].trim();
+        let value = unquote(line[position + 1..].trim());
+
+        let key = match &section {
+            Some(section) => format!("{}/{}", section, name),
+            None => name.to_string(),
+        };
+        pairs.insert(number + 1, key, value.to_string());
+    }
+    Ok(pairs)
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_keys_under_their_section() {
+        let contents =
+            "; comment\nname = app\n[db]\nurl = \"jdbc:h2:mem\"\n# comment\nport: 5432\n";
+        assert_eq!(
+            parse(contents).unwrap().into_pairs(),
+            [
+                ("name".to_string(), "app".to_string()),
+                ("db/url".to_string(), "jdbc:h2:mem".to_string()),
+                ("db/port".to_string(), "5432".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_last_value_of_a_repeated_key() {
+        let pairs = parse("[db]\nport = 1\nport = 2\n").unwrap();
+        assert_eq!(pairs.duplicates.len(), 1);
+        assert_eq!(pairs.duplicates[0].first_line, 2);
+        assert_eq!(pairs.duplicates[0].line, 3);
+        assert_eq!(
+            pairs.into_pairs(),
+            [("db/port".to_string(), "2".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_a_malformed_entry() {
+        assert_eq!(
+            parse("[db]\nport\n").unwrap_err(),
+            "line 2: expected key = value"
+        );
+        assert_eq!(
+            parse("[db\n").unwrap_err(),
+            "line 1: unterminated section header"
+        );
+    }
+}
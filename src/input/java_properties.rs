@@ -0,0 +1,182 @@
+use super::duplicates::Pairs;
+
+/// Parses the contents of a Java `.properties` file into key value pairs in
+/// file order, following the rules of `java.util.Properties::load`. A key
+/// that appears more than once keeps its first position and its last value.
+pub fn parse(contents: &str) -> Pairs {
+    let mut pairs = Pairs::default();
+
+    for (line_number, line) in logical_lines(contents) {
+        let (key, value) = split_line(&line);
+        pairs.insert(line_number, unescape(key), unescape(value));
+    }
+    pairs
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0c')
+}
+
+/// Joins lines ending in an odd number of backslashes with the line after
+/// them, and drops blank lines and comments, along with the line number each
+/// logical line starts on.
+fn logical_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim_start_matches(is_whitespace);
+        if current.is_none() && (line.is_empty() || line.starts_with(['#', '!'])) {
+            continue;
+        }
+
+        let trailing = line.chars().rev().take_while(|c| *c == '\\').count();
+        let (start, mut logical) = current.take().unwrap_or((number + 1, String::new()));
+        if trailing % 2 == 1 {
+            logical.push_str(&line[..line.len() - 1]);
+            current = Some((start, logical));
+        } else {
+            logical.push_str(line);
+            lines.push((start, logical));
+        }
+    }
+
+    // A continuation on the last line of the file ends the entry
+    lines.extend(current);
+    lines
+}
+
+/// Splits a logical line at the first unescaped `=`, `:` or whitespace.
+fn split_line(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+    for (position, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || is_whitespace(c) {
+            key_end = position;
+            break;
+        }
+    }
+
+    let rest = line[key_end..].trim_start_matches(is_whitespace);
+    let rest = rest
+        .strip_prefix(['=', ':'])
+        .unwrap_or(rest)
+        .trim_start_matches(is_whitespace);
+    (&line[..key_end], rest)
+}
+
+/// Resolves the escapes of a key or value, including `\uXXXX` escapes where
+/// a pair of escaped UTF-16 surrogates makes up a single character.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut high_surrogate: Option<u16> = None;
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&'u') {
+            chars.next();
+            let digits: String = (&mut chars).take(4).collect();
+            // A malformed escape is kept as written, as it cannot be decoded
+            let Ok(unit) = u16::from_str_radix(&digits, 16) else {
+                result.push_str("\\u");
+                result.push_str(&digits);
+                continue;
+            };
+            let units: Vec<u16> = high_surrogate.take().into_iter().chain([unit]).collect();
+            match char::decode_utf16(units.iter().copied()).collect::<Result<String, _>>() {
+                Ok(decoded) => result.push_str(&decoded),
+                Err(_) if (0xD800..0xDC00).contains(&unit) => high_surrogate = Some(unit),
+                Err(_) => result.push(char::REPLACEMENT_CHARACTER),
+            }
+            continue;
+        }
+        if high_surrogate.take().is_some() {
+            result.push(char::REPLACEMENT_CHARACTER);
+        }
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\x0c'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    if high_surrogate.is_some() {
+        result.push(char::REPLACEMENT_CHARACTER);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(contents: &str) -> Vec<(String, String)> {
+        parse(contents).into_pairs()
+    }
+
+    #[test]
+    fn splits_on_equals_colon_or_whitespace() {
+        let contents = "# comment\n! comment\na=1\nb : 2\nc 3\nd\n";
+        assert_eq!(
+            pairs(contents),
+            [
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+                ("c".to_string(), "3".to_string()),
+                ("d".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_continued_lines() {
+        let contents = "list = a, \\\n    b, \\\n    c\nescaped = x\\\\\nnext = y\n";
+        assert_eq!(
+            pairs(contents),
+            [
+                ("list".to_string(), "a, b, c".to_string()),
+                ("escaped".to_string(), "x\\".to_string()),
+                ("next".to_string(), "y".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_escapes_in_keys_and_values() {
+        assert_eq!(
+            pairs("a\\=b\\ c = tab\\there\n"),
+            [("a=b c".to_string(), "tab\there".to_string())]
+        );
+        assert_eq!(
+            pairs("name = caf\\u00e9 \\ud83d\\ude00\n"),
+            [("name".to_string(), "café 😀".to_string())]
+        );
+        assert_eq!(
+            pairs("bad = \\uzz\n"),
+            [("bad".to_string(), "\\uzz".to_string())]
+        );
+    }
+
+    #[test]
+    fn records_repeated_keys() {
+        let parsed = parse("a=1\nb=2\na=3\n");
+        assert_eq!(parsed.duplicates.len(), 1);
+        assert_eq!(parsed.duplicates[0].key, "a");
+        assert_eq!(
+            parsed.into_pairs(),
+            [
+                ("a".to_string(), "3".to_string()),
+                ("b".to_string(), "2".to_string()),
+            ]
+        );
+    }
+}
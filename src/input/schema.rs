@@ -0,0 +1,17 @@
+use serde_json::Value;
+
+/// Checks a parsed input file against a JSON Schema, describing each
+/// violation by the JSON pointer of the offending value and what is wrong
+/// with it.
+pub fn violations(schema: &Value, instance: &Value) -> Result<Vec<String>, String> {
+    let validator = jsonschema::validator_for(schema).map_err(|e| e.to_string())?;
+
+    Ok(validator
+        .iter_errors(instance)
+        .map(|error| {
+            let path = error.instance_path().to_string();
+            let path = if path.is_empty() { "/" } else { path.as_str() };
+            format!("{}: {}", path, error)
+        })
+        .collect())
+}
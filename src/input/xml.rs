@@ -0,0 +1,128 @@
+use roxmltree::{Document, Node, ParsingOptions};
+
+/// Parses an XML document into key paths and values in document order.
+///
+/// A Java XML properties file, with a `<properties>` root of `<entry key="..">`
+/// elements, gives its entries with dotted keys mapped to slash separated
+/// paths. Any other document is flattened below its root element: each
+/// element adds a path segment, repeated sibling elements are indexed like
+/// YAML sequences, attributes become keys below their element and text
+/// becomes the value of the element's own key.
+pub fn parse(contents: &str) -> Result<Vec<(String, String)>, String> {
+    // Java XML properties files always declare the properties DTD
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(contents, options).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+
+    let mut pairs = Vec::new();
+    if root.has_tag_name("properties") {
+        for entry in root.children().filter(|node| node.has_tag_name("entry")) {
+            let key = entry
+                .attribute("key")
+                .ok_or("<entry> without a key attribute")?;
+            pairs.push((
+                key.replace('.', "/"),
+                entry.text().unwrap_or_default().to_string(),
+            ));
+        }
+    } else {
+        flatten_children(root, "", &mut pairs);
+    }
+    Ok(pairs)
+}
+
+fn flatten_children(node: Node, path: &str, pairs: &mut Vec<(String, String)>) {
+    let children: Vec<_> = node.children().filter(Node::is_element).collect();
+
+    for child in &children {
+        let name = child.tag_name().name();
+        let siblings: Vec<_> = children
+            .iter()
+            .filter(|other| other.tag_name().name() == name)
+            .collect();
+        let segment = match siblings.iter().position(|other| *other == child) {
+            Some(index) if siblings.len() > 1 => format!("{}[{}]", name, index),
+            _ => name.to_string(),
+        };
+        let child_path = if path.is_empty() {
+            segment
+        } else {
+            format!("{}/{}", path, segment)
+        };
+        flatten_element(*child, &child_path, pairs);
+    }
+}
+
+fn flatten_element(node: Node, path: &str, pairs: &mut Vec<(String, String)>) {
+    let text: String = node
+        .children()
+        .filter(Node::is_text)
+        .filter_map(|text| text.text())
+        .collect();
+    let has_children = node.children().any(|child| child.is_element());
+    if !text.trim().is_empty() || (!has_children && node.attributes().len() == 0) {
+        pairs.push((path.to_string(), text.trim().to_string()));
+    }
+
+    for attribute in node.attributes() {
+        pairs.push((
+            format!("{}/{}", path, attribute.name()),
+            attribute.value().to_string(),
+        ));
+    }
+    flatten_children(node, path, pairs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(contents: &str) -> Vec<(String, String)> {
+        parse(contents).unwrap()
+    }
+
+    #[test]
+    fn reads_java_xml_properties_with_dotted_keys() {
+        let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE properties SYSTEM "http://java.sun.com/dtd/properties.dtd">
+<properties>
+  <entry key="db.url">jdbc:h2:mem</entry>
+  <entry key="db.user"></entry>
+</properties>"#;
+        assert_eq!(
+            pairs(contents),
+            [
+                ("db/url".to_string(), "jdbc:h2:mem".to_string()),
+                ("db/user".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_an_entry_without_a_key() {
+        assert!(parse("<properties><entry>value</entry></properties>").is_err());
+    }
+
+    #[test]
+    fn flattens_elements_attributes_and_repeated_siblings() {
+        let contents = r#"<config>
+  <server port="8080">
+    <host>a</host>
+    <host>b</host>
+  </server>
+  <name>app</name>
+</config>"#;
+        assert_eq!(
+            pairs(contents),
+            [
+                ("server/port".to_string(), "8080".to_string()),
+                ("server/host[0]".to_string(), "a".to_string()),
+                ("server/host[1]".to_string(), "b".to_string()),
+                ("name".to_string(), "app".to_string()),
+            ]
+        );
+    }
+}
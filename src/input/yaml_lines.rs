@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+/// Walks the keys of block style YAML by their indentation, calling `visit`
+/// with the index of the document, the key path in the form properties are
+/// flattened to, such as `server/ports[0]/name`, and the line it is set on,
+/// counted from 1. Flow style mappings are not looked into.
+pub fn scan(contents: &str, mut visit: impl FnMut(usize, &str, usize)) {
+    // Each open mapping key or list item, by its indentation
+    let mut parents: Vec<(usize, String, bool)> = Vec::new();
+    let mut items: HashMap<String, usize> = HashMap::new();
+    let mut block_scalar: Option<usize> = None;
+    let mut document = 0;
+
+    for (number, line) in contents.lines().enumerate() {
+        if line.starts_with("---") {
+            parents.clear();
+            items.clear();
+            block_scalar = None;
+            document += 1;
+            continue;
+        }
+
+        let content = line.trim_start_matches(' ');
+        let mut indent = line.len() - content.len();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        match block_scalar {
+            Some(key_indent) if indent > key_indent => continue,
+            _ => block_scalar = None,
+        }
+
+        let mut content = content;
+        while let Some(rest) = content
+            .strip_prefix('-')
+            .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        {
+            while parents.last().is_some_and(|(parent_indent, _, is_item)| {
+                *parent_indent > indent || (*parent_indent == indent && *is_item)
+            }) {
+                parents.pop();
+            }
+            let parent = parents
+                .last()
+                .map(|(_, path, _)| path.clone())
+                .unwrap_or_default();
+            let count = items.entry(parent.clone()).or_default();
+            parents.push((indent, format!("{}[{}]", parent, count), true));
+            *count += 1;
+
+            let trimmed = rest.trim_start_matches(' ');
+            indent += 1 + rest.len() - trimmed.len();
+            content = trimmed;
+        }
+
+        let Some((key, value)) = split_key(content) else {
+            continue;
+        };
+        if key == "<<" {
+            continue;
+        }
+
+        while parents
+            .last()
+            .is_some_and(|(parent_indent, _, _)| *parent_indent >= indent)
+        {
+            parents.pop();
+        }
+        let path = match parents.last() {
+            Some((_, parent, _)) => format!("{}/{}", parent, key),
+            None => key.to_string(),
+        };
+
+        visit(document, &path, number + 1);
+        if value.starts_with(['|', '>']) {
+            block_scalar = Some(indent);
+        }
+        parents.push((indent, path, false));
+    }
+}
+
+/// Splits a `key: value` line, with the key possibly quoted.
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    let (key, rest) = match content.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = content[1..].find(quote)? + 1;
+            (&content[1..end], content[end + 1..].strip_prefix(':')?)
+        }
+        _ => {
+            let end = content
+                .find(": ")
+                .or_else(|| content.strip_suffix(':').map(|key| key.len()))?;
+            (&content[..end], &content[end + 1..])
+        }
+    };
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((key, rest.trim_start()))
+}
@@ -0,0 +1,180 @@
+use regex::Regex;
+use std::{fs, path::Path};
+
+use crate::{
+    error::{Error, Result},
+    pattern,
+    properties::ConsulProperties,
+};
+
+/// A line of a key mapping file, rewriting the file keys it matches into
+/// the Consul layout.
+#[derive(Debug, Clone)]
+pub struct KeyMapping {
+    from: Regex,
+    /// The replacement, where `$1` and `${1}` stand for the captures of
+    /// `from`
+    to: String,
+}
+
+impl KeyMapping {
+    /// The key rewritten by the rule, or nothing when it does not match.
+    pub fn apply(&self, key: &str) -> Option<String> {
+        self.from
+            .is_match(key)
+            .then(|| self.from.replace(key, self.to.as_str()).into_owned())
+    }
+}
+
+/// Rewrites the keys of the properties with the first rule that matches
+/// each, leaving the others as they are.
+pub fn apply(properties: &[ConsulProperties], mappings: &[KeyMapping]) -> Vec<ConsulProperties> {
+    properties
+        .iter()
+        .map(|item| ConsulProperties {
+            key: mappings
+                .iter()
+                .find_map(|mapping| mapping.apply(&item.key))
+                .unwrap_or_else(|| item.key.clone()),
+            value: item.value.clone(),
+        })
+        .collect()
+}
+
+/// Reads the rules of a key mapping file, one `FROM -> TO` per line. A glob
+/// `FROM` gives each wildcard to the wildcard at the same position in `TO`,
+/// so `legacy/db/* -> datasource/*` maps `legacy/db/url` to
+/// `datasource/url`. A `re:` regex `FROM` gives its captures to `$1`, `$2`
+/// and so on in `TO`. Blank lines and `#` comments are skipped.
+pub fn load(mapping_file: Option<&Path>) -> Result<Vec<KeyMapping>> {
+    let Some(file_path) = mapping_file else {
+        return Ok(Vec::new());
+    };
+    let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+
+    let mut mappings = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_error = |e: String| Error::read(file_path, format!("line {}: {}", number + 1, e));
+        let Some((from, to)) = line.split_once("->") else {
+            return Err(line_error("expected FROM -> TO".to_string()));
+        };
+        let (from, to) = (from.trim(), to.trim());
+        let mapping = match from.strip_prefix("re:") {
+            Some(regex) => KeyMapping {
+                from: Regex::new(regex).map_err(|e| line_error(e.to_string()))?,
+                to: to.to_string(),
+            },
+            None => glob_mapping(from, to).map_err(line_error)?,
+        };
+        mappings.push(mapping);
+    }
+    Ok(mappings)
+}
+
+/// Maps a glob to a key with as many wildcards, which are replaced in order
+/// by what the wildcards of the glob matched.
+fn glob_mapping(from: &str, to: &str) -> std::result::Result<KeyMapping, String> {
+    let from = pattern::glob_to_capturing_regex(from);
+    let mut replacement = String::new();
+    let mut wildcards = 0;
+    let mut chars = to.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => {
+                // `**/` matches its own slash, when it matches any folder
+                if c == '*' && chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                    }
+                }
+                wildcards += 1;
+                replacement.push_str(&format!("${{{}}}", wildcards));
+            }
+            '$' => replacement.push_str("$$"),
+            c => replacement.push(c),
+        }
+    }
+    if wildcards != from.captures_len() - 1 {
+        return Err(format!(
+            "{} has {} wildcards where the key it maps has {}",
+            to,
+            wildcards,
+            from.captures_len() - 1
+        ));
+    }
+    Ok(KeyMapping {
+        from,
+        to: replacement,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        env, process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn mappings(contents: &str) -> Result<Vec<KeyMapping>> {
+        // Tests run in parallel, so each one writes a file of its own
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+        let mapping_file = env::temp_dir().join(format!(
+            "consul-help-{}-{}.map",
+            process::id(),
+            FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&mapping_file, contents).unwrap();
+        let mappings = load(Some(&mapping_file));
+        fs::remove_file(&mapping_file).unwrap();
+        mappings
+    }
+
+    #[test]
+    fn rewrites_keys_with_the_first_matching_rule() {
+        let mappings = mappings(
+            "# comment\n\nlegacy/db/* -> datasource/*\nlegacy/** -> app/**\nre:^(\\w+)_(\\w+)$ -> $2/$1\n",
+        )
+        .unwrap();
+        let properties = [
+            property("legacy/db/url", "a"),
+            property("legacy/cache/ttl", "b"),
+            property("url_db", "c"),
+            property("name", "d"),
+        ];
+        assert_eq!(
+            apply(&properties, &mappings),
+            [
+                property("datasource/url", "a"),
+                property("app/cache/ttl", "b"),
+                property("db/url", "c"),
+                property("name", "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_dollar_signs_in_glob_targets_literal() {
+        let mappings = mappings("price/* -> $cost/*\n").unwrap();
+        assert_eq!(mappings[0].apply("price/eur").unwrap(), "$cost/eur");
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(mappings("a/*\n").is_err());
+        assert!(mappings("a/* -> b/*/*\n").is_err());
+        assert!(mappings("re:( -> b\n").is_err());
+    }
+}
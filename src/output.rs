@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt,
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
+
+/// Nothing but errors is printed.
+const QUIET: u8 = 0;
+/// The results are printed, which is the default.
+const NORMAL: u8 = 1;
+/// Each key read from Consul or an input file is traced on stderr as well.
+const TRACE: u8 = 2;
+/// The HTTP requests to Consul and their responses are logged as well.
+const HTTP: u8 = 3;
+
+static LEVEL: AtomicU8 = AtomicU8::new(NORMAL);
+
+/// Sets how much is printed from the -q and -d flags.
+pub fn init(quiet: bool, debug: u8) {
+    let level = match (quiet, debug) {
+        (true, _) => QUIET,
+        (false, 0) => NORMAL,
+        (false, 1) => TRACE,
+        (false, _) => HTTP,
+    };
+    LEVEL.store(level, Ordering::Relaxed);
+
+    if level >= HTTP {
+        let targets = Targets::new()
+            .with_target("rustify", LevelFilter::DEBUG)
+            .with_target("consulrs", LevelFilter::DEBUG);
+        tracing_subscriber::registry()
+            .with(fmt::layer().with_writer(std::io::stderr))
+            .with(targets)
+            .init();
+    }
+}
+
+pub fn is_quiet() -> bool {
+    LEVEL.load(Ordering::Relaxed) == QUIET
+}
+
+pub fn is_tracing() -> bool {
+    LEVEL.load(Ordering::Relaxed) >= TRACE
+}
+
+/// Prints to stdout like `print!`, unless quiet.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            print!($($arg)*);
+        }
+    };
+}
+
+/// Prints a line to stdout like `println!`, unless quiet.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Prints a progress line to stderr like `eprintln!`, unless quiet, so it
+/// never ends up in a report or file contents written to stdout.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Prints a trace line to stderr when running with -d.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::output::is_tracing() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use {out, outln, progress, trace};
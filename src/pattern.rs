@@ -0,0 +1,97 @@
+use regex::Regex;
+
+/// Whether a key segment contains shell style wildcards.
+pub fn is_glob(segment: &str) -> bool {
+    segment.contains(['*', '?'])
+}
+
+/// Translates a shell style glob into an anchored regex, where `*` matches
+/// any run of characters and `?` a single character, neither crossing a `/`,
+/// while `**` matches across folders and `**/` any number of folders.
+pub fn glob_to_regex(glob: &str) -> Regex {
+    translate(glob, false)
+}
+
+/// Like `glob_to_regex`, with every wildcard in a capture group of its own,
+/// numbered from 1 in the order of the glob.
+pub fn glob_to_capturing_regex(glob: &str) -> Regex {
+    translate(glob, true)
+}
+
+fn translate(glob: &str, capture: bool) -> Regex {
+    let mut pattern = String::from("^");
+    let push_wildcard = |pattern: &mut String, wildcard: &str| match capture {
+        true => pattern.push_str(&format!("({})", wildcard)),
+        false => pattern.push_str(wildcard),
+    };
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    push_wildcard(&mut pattern, "(?:.*/)?");
+                } else {
+                    push_wildcard(&mut pattern, ".*");
+                }
+            }
+            '*' => push_wildcard(&mut pattern, "[^/]*"),
+            '?' => push_wildcard(&mut pattern, "[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    Regex::new(&pattern).expect("An escaped glob is always a valid regex")
+}
+
+/// Parses a key pattern, which is a glob unless it starts with `re:`, in
+/// which case the rest is an unanchored regex.
+pub fn parse_key_pattern(value: &str) -> Result<Regex, String> {
+    match value.strip_prefix("re:") {
+        Some(regex) => Regex::new(regex).map_err(|e| e.to_string()),
+        None => Ok(glob_to_regex(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_wildcards_stay_within_a_segment() {
+        assert!(is_glob("app-*") && is_glob("app?") && !is_glob("app"));
+        let regex = glob_to_regex("config/app-?/*");
+        assert!(regex.is_match("config/app-1/db"));
+        assert!(!regex.is_match("config/app-1/db/url"));
+        assert!(!regex.is_match("config/app-12/db"));
+        assert!(!glob_to_regex("a.b").is_match("axb"));
+    }
+
+    #[test]
+    fn double_wildcards_cross_folders() {
+        let regex = glob_to_regex("db/**");
+        assert!(regex.is_match("db/url") && regex.is_match("db/pool/size"));
+        let regex = glob_to_regex("**/password");
+        assert!(regex.is_match("password") && regex.is_match("db/admin/password"));
+        assert!(!regex.is_match("db/password-hint"));
+    }
+
+    #[test]
+    fn key_patterns_are_globs_unless_regexes() {
+        let glob = parse_key_pattern("db/*").unwrap();
+        assert!(glob.is_match("db/url") && !glob.is_match("old/db/url"));
+        let regex = parse_key_pattern("re:(url|host)$").unwrap();
+        assert!(regex.is_match("old/db/url"));
+        assert!(parse_key_pattern("re:(").is_err());
+    }
+
+    #[test]
+    fn capturing_regexes_number_the_wildcards() {
+        let regex = glob_to_capturing_regex("legacy/*/**");
+        let captures = regex.captures("legacy/db/pool/size").unwrap();
+        assert_eq!(&captures[1], "db");
+        assert_eq!(&captures[2], "pool/size");
+    }
+}
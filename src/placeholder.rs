@@ -0,0 +1,43 @@
+use std::{borrow::Cow, env};
+
+/// Whether a value holds a placeholder filled in when the configuration is
+/// deployed, such as `${DB_PASSWORD}` or `{{ vault "secret/db" }}`, which a
+/// resolved value in Consul never equals.
+pub fn contains(value: &str) -> bool {
+    [("${", "}"), ("{{", "}}")].iter().any(|(open, close)| {
+        value
+            .find(open)
+            .is_some_and(|start| value[start + open.len()..].contains(close))
+    })
+}
+
+/// Replaces each `${NAME}` in a value with the environment variable of that
+/// name, or with the default of a `${NAME:default}` when it is not set, the
+/// way Spring resolves them. Placeholders that cannot be resolved are kept.
+pub fn resolve(value: &str) -> Cow<'_, str> {
+    if !value.contains("${") {
+        return Cow::Borrowed(value);
+    }
+
+    let mut resolved = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[start..start + length + 1];
+        let (name, default) = match placeholder[2..length].split_once(':') {
+            Some((name, default)) => (name, Some(default)),
+            None => (&placeholder[2..length], None),
+        };
+        resolved.push_str(&rest[..start]);
+        match (env::var(name), default) {
+            (Ok(variable), _) => resolved.push_str(&variable),
+            (Err(_), Some(default)) => resolved.push_str(default),
+            (Err(_), None) => resolved.push_str(placeholder),
+        }
+        rest = &rest[start + length + 1..];
+    }
+    resolved.push_str(rest);
+    Cow::Owned(resolved)
+}
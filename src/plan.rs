@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    cli::ConnectionOpts,
+    consul::{self, TxnOperation},
+    error::{Error, Result},
+    output::{outln, progress},
+    properties::ConsulProperties,
+};
+
+/// A single change to be applied to a key under the plan's app prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Operation {
+    Create {
+        key: String,
+        value: String,
+    },
+    Update {
+        key: String,
+        old_value: String,
+        new_value: String,
+    },
+    Delete {
+        key: String,
+        old_value: String,
+    },
+}
+
+impl Operation {
+    pub fn key(&self) -> &str {
+        match self {
+            Operation::Create { key, .. }
+            | Operation::Update { key, .. }
+            | Operation::Delete { key, .. } => key,
+        }
+    }
+}
+
+/// The set of writes a mutating command performs, sorted by key so the
+/// printed plan is stable between runs.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub app_prefix: String,
+    pub operations: Vec<Operation>,
+}
+
+impl Plan {
+    pub fn new(app_prefix: &str, mut operations: Vec<Operation>) -> Plan {
+        operations.sort_by(|a, b| a.key().cmp(b.key()));
+        Plan {
+            app_prefix: app_prefix.to_string(),
+            operations,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn print(&self) {
+        for operation in &self.operations {
+            outln!("{}", self.describe(operation));
+        }
+        outln!("{}", self.summary());
+    }
+
+    fn describe(&self, operation: &Operation) -> String {
+        match operation {
+            Operation::Create { key, value } => {
+                format!("+ {}/{} = {:?}", self.app_prefix, key, value)
+            }
+            Operation::Update {
+                key,
+                old_value,
+                new_value,
+            } => format!(
+                "~ {}/{} = {:?} -> {:?}",
+                self.app_prefix, key, old_value, new_value
+            ),
+            Operation::Delete { key, old_value } => {
+                format!("- {}/{} = {:?}", self.app_prefix, key, old_value)
+            }
+        }
+    }
+
+    /// Asks on stdin whether each operation should be applied, with the same
+    /// y/n/a/q answers as `git add -p`, and keeps only the accepted ones.
+    pub fn select_interactively(self) -> Result<Plan> {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut accepted = Vec::new();
+        let mut accept_all = false;
+
+        for operation in &self.operations {
+            if accept_all {
+                accepted.push(operation.clone());
+                continue;
+            }
+
+            println!("{}", self.describe(operation));
+            let answer = loop {
+                print!("Apply this change [y,n,a,q]? ");
+                io::stdout()
+                    .flush()
+                    .map_err(|e| Error::write("stdout".as_ref(), e))?;
+
+                let Some(line) = lines.next() else {
+                    break "q".to_string();
+                };
+                let answer = line.map_err(|e| Error::read("stdin".as_ref(), e))?;
+                match answer.trim() {
+                    "y" | "n" | "a" | "q" => break answer.trim().to_string(),
+                    _ => {
+                        println!("y - apply, n - skip, a - apply this and all remaining, q - quit")
+                    }
+                }
+            };
+
+            match answer.as_str() {
+                "y" => accepted.push(operation.clone()),
+                "a" => {
+                    accepted.push(operation.clone());
+                    accept_all = true;
+                }
+                "q" => break,
+                _ => {}
+            }
+        }
+
+        Ok(Plan {
+            app_prefix: self.app_prefix,
+            operations: accepted,
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&Operation) -> bool| self.operations.iter().filter(|op| f(op)).count();
+        format!(
+            "Plan: {} to create, {} to update, {} to delete.",
+            count(|op| matches!(op, Operation::Create { .. })),
+            count(|op| matches!(op, Operation::Update { .. })),
+            count(|op| matches!(op, Operation::Delete { .. })),
+        )
+    }
+
+    pub async fn apply(&self, client: &consul::Client) -> Result<()> {
+        let mut to_write = Vec::new();
+        let mut to_delete = Vec::new();
+
+        for operation in &self.operations {
+            match operation {
+                Operation::Create { key, value }
+                | Operation::Update {
+                    key,
+                    new_value: value,
+                    ..
+                } => to_write.push(ConsulProperties {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+                Operation::Delete { key, old_value } => to_delete.push(ConsulProperties {
+                    key: key.clone(),
+                    value: old_value.clone(),
+                }),
+            }
+        }
+
+        consul::write_properties(client, &self.app_prefix, &to_write).await?;
+        consul::delete_properties(client, &self.app_prefix, &to_delete).await
+    }
+}
+
+/// A plan saved by the plan command for a later apply, together with the
+/// modify index every key had when the plan was made.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanFile {
+    pub consul_host: String,
+    /// The datacenter, namespace and admin partition the plan was made in,
+    /// missing from plans made without them
+    #[serde(default)]
+    pub datacenter: Option<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub partition: Option<String>,
+    pub app_prefix: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub changes: Vec<PlannedChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlannedChange {
+    #[serde(flatten)]
+    pub operation: Operation,
+    /// 0 when the key did not exist
+    pub modify_index: u64,
+}
+
+impl PlanFile {
+    /// Pairs the operations of the plan with the indexes, keyed relative to
+    /// the app prefix, that Consul reported when the plan was made.
+    pub fn new(
+        consul_host: &str,
+        connection: &ConnectionOpts,
+        created_at: u64,
+        plan: Plan,
+        indexes: &HashMap<String, u64>,
+    ) -> PlanFile {
+        let changes = plan
+            .operations
+            .into_iter()
+            .map(|operation| PlannedChange {
+                modify_index: indexes.get(operation.key()).copied().unwrap_or(0),
+                operation,
+            })
+            .collect();
+
+        PlanFile {
+            consul_host: consul_host.to_string(),
+            datacenter: connection.datacenter.clone(),
+            namespace: connection.consul_namespace.clone(),
+            partition: connection.partition.clone(),
+            app_prefix: plan.app_prefix,
+            created_at,
+            changes,
+        }
+    }
+
+    pub fn load(file_path: &PathBuf) -> Result<PlanFile> {
+        progress!("Loading plan from file: {}", file_path.display());
+
+        let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+        serde_json::from_str(&contents).map_err(|e| Error::read(file_path, e))
+    }
+
+    pub fn save(&self, file_path: &PathBuf) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(Error::serialize)?;
+        fs::write(file_path, contents).map_err(|e| Error::write(file_path, e))
+    }
+
+    pub fn plan(&self) -> Plan {
+        Plan::new(
+            &self.app_prefix,
+            self.changes
+                .iter()
+                .map(|change| change.operation.clone())
+                .collect(),
+        )
+    }
+
+    /// Keys whose modify index no longer matches the one the plan was made
+    /// against.
+    pub fn stale_keys(&self, indexes: &HashMap<String, u64>) -> Vec<&str> {
+        self.changes
+            .iter()
+            .filter(|change| {
+                indexes.get(change.operation.key()).copied().unwrap_or(0) != change.modify_index
+            })
+            .map(|change| change.operation.key())
+            .collect()
+    }
+
+    /// The changes as check-and-set operations, so each one fails if the key
+    /// was modified after the plan was made.
+    pub fn txn_operations(&self) -> Vec<TxnOperation> {
+        self.changes
+            .iter()
+            .map(|change| {
+                let key = format!("{}/{}", self.app_prefix, change.operation.key());
+                match &change.operation {
+                    Operation::Create { value, .. }
+                    | Operation::Update {
+                        new_value: value, ..
+                    } => TxnOperation::cas(key, value, change.modify_index),
+                    Operation::Delete { .. } => TxnOperation::delete_cas(key, change.modify_index),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_file() -> PlanFile {
+        let change = |operation, modify_index| PlannedChange {
+            operation,
+            modify_index,
+        };
+        PlanFile {
+            consul_host: "http://127.0.0.1:8500".to_string(),
+            datacenter: None,
+            namespace: None,
+            partition: None,
+            app_prefix: "config/app".to_string(),
+            created_at: 0,
+            changes: vec![
+                change(
+                    Operation::Create {
+                        key: "new".to_string(),
+                        value: "1".to_string(),
+                    },
+                    0,
+                ),
+                change(
+                    Operation::Update {
+                        key: "changed".to_string(),
+                        old_value: "1".to_string(),
+                        new_value: "2".to_string(),
+                    },
+                    10,
+                ),
+                change(
+                    Operation::Delete {
+                        key: "gone".to_string(),
+                        old_value: "3".to_string(),
+                    },
+                    20,
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn finds_the_keys_modified_after_the_plan() {
+        let plan_file = plan_file();
+        let indexes = HashMap::from([("changed".to_string(), 10), ("gone".to_string(), 20)]);
+        assert!(plan_file.stale_keys(&indexes).is_empty());
+
+        let indexes = HashMap::from([
+            ("new".to_string(), 5),
+            ("changed".to_string(), 11),
+            ("gone".to_string(), 20),
+        ]);
+        assert_eq!(plan_file.stale_keys(&indexes), ["new", "changed"]);
+
+        // A key deleted since the plan no longer has an index
+        assert_eq!(
+            plan_file.stale_keys(&HashMap::from([("changed".to_string(), 10)])),
+            ["gone"]
+        );
+    }
+
+    #[test]
+    fn checks_the_planned_index_of_every_key() {
+        let operations = serde_json::to_value(plan_file().txn_operations()).unwrap();
+        assert_eq!(
+            operations,
+            serde_json::json!([
+                {"KV": {"Verb": "cas", "Key": "config/app/new", "Value": "MQ==", "Index": 0}},
+                {"KV": {"Verb": "cas", "Key": "config/app/changed", "Value": "Mg==", "Index": 10}},
+                {"KV": {"Verb": "delete-cas", "Key": "config/app/gone", "Index": 20}},
+            ])
+        );
+    }
+
+    #[test]
+    fn reads_plans_saved_without_the_connection_scope() {
+        let plan_file: PlanFile = serde_json::from_str(
+            r#"{"consul_host": "h", "app_prefix": "p", "created_at": 1, "changes": [
+                {"action": "delete", "key": "a", "old_value": "1", "modify_index": 4}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan_file.datacenter, None);
+        assert_eq!(plan_file.changes[0].modify_index, 4);
+        assert_eq!(
+            plan_file.plan().operations,
+            [Operation::Delete {
+                key: "a".to_string(),
+                old_value: "1".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use serde_yaml::{Mapping, Value};
+use std::{fs::File, io::Write, path::PathBuf};
+
+use crate::{
+    cli::{ArrayStyle, InputOpts, NullPolicy},
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsulProperties {
+    pub key: String,
+    pub value: String,
+}
+
+/// Flattens a YAML document into properties keyed by the slash separated
+/// path to each scalar, with sequences laid out in the style of the input
+/// options, null scalars kept as their policy says and the nodes at
+/// --max-depth or an --opaque key kept whole.
+pub fn flatten_value(value: &Value, input_opts: &InputOpts) -> Vec<ConsulProperties> {
+    let mut result = Vec::new();
+    flatten_yaml(value, &mut result, String::new(), 0, input_opts);
+
+    result
+        .into_iter()
+        .map(|item| ConsulProperties {
+            key: item.0,
+            value: item.1,
+        })
+        .collect()
+}
+
+fn flatten_yaml(
+    value: &Value,
+    properties: &mut Vec<(String, String)>,
+    prefix: String,
+    depth: u32,
+    input_opts: &InputOpts,
+) {
+    if !is_scalar(value)
+        && (input_opts.max_depth == Some(depth)
+            || input_opts
+                .opaque
+                .iter()
+                .any(|regex| regex.is_match(&prefix)))
+    {
+        properties.push((prefix, canonical_json(value)));
+        return;
+    }
+
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                if let Value::String(key_str) = key {
+                    let new_prefix = if prefix.is_empty() {
+                        key_str.clone()
+                    } else {
+                        format!("{}/{}", prefix, key_str)
+                    };
+                    flatten_yaml(value, properties, new_prefix, depth + 1, input_opts);
+                }
+            }
+        }
+        Value::Sequence(sequence) => match input_opts.array_style {
+            // Only a list of scalars can be joined into a single value
+            ArrayStyle::Comma if sequence.iter().all(is_scalar) => {
+                let values: Vec<_> = sequence.iter().map(value_to_string).collect();
+                properties.push((prefix, values.join(",")));
+            }
+            ArrayStyle::Json => {
+                let json =
+                    serde_json::to_string(sequence).unwrap_or_else(|_| value_to_string(value));
+                properties.push((prefix, json));
+            }
+            ArrayStyle::Explode => {
+                for (index, value) in sequence.iter().enumerate() {
+                    let new_prefix = if prefix.is_empty() {
+                        index.to_string()
+                    } else {
+                        format!("{}/{}", prefix, index)
+                    };
+                    flatten_yaml(value, properties, new_prefix, depth + 1, input_opts);
+                }
+            }
+            ArrayStyle::Indexed | ArrayStyle::Comma => {
+                for (index, value) in sequence.iter().enumerate() {
+                    let new_prefix = format!("{}[{}]", prefix, index);
+                    flatten_yaml(value, properties, new_prefix, depth + 1, input_opts);
+                }
+            }
+        },
+        Value::Null => match input_opts.null_policy {
+            NullPolicy::Skip => {}
+            NullPolicy::Empty => properties.push((prefix, String::new())),
+            NullPolicy::Literal => properties.push((prefix, value_to_string(value))),
+        },
+        _ => {
+            properties.push((prefix, value_to_string(value)));
+        }
+    }
+}
+
+/// Serializes a node as JSON with the keys of its mappings in order, so the
+/// same document always gives the same value.
+fn canonical_json(value: &Value) -> String {
+    serde_json::to_string(&sort_yaml(value.clone())).unwrap_or_else(|_| value_to_string(value))
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Mapping(_) | Value::Sequence(_) | Value::Tagged(_)
+    )
+}
+
+pub fn write_properties_file(file_path: &PathBuf, properties: &[ConsulProperties]) -> Result<()> {
+    let mut file = File::create(file_path).map_err(|e| Error::write(file_path, e))?;
+    for item in properties {
+        let line = format!("{}={}\n", item.key, item.value);
+        file.write_all(line.as_bytes())
+            .map_err(|e| Error::write(file_path, e))?;
+    }
+    Ok(())
+}
+
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+pub fn unflatten_yaml(properties: &[ConsulProperties]) -> Value {
+    let mut root = Value::Mapping(Mapping::new());
+    for item in properties {
+        if item.key.is_empty() || item.key.ends_with('/') {
+            continue;
+        }
+        insert_yaml(
+            &mut root,
+            &parse_key_path(&item.key),
+            string_to_value(&item.value),
+        );
+    }
+    root
+}
+
+/// Recursively orders mapping keys alphabetically.
+pub fn sort_yaml(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => {
+            let mut entries: Vec<_> = mapping.into_iter().collect();
+            entries.sort_by_key(|(key, _)| value_to_string(key));
+            Value::Mapping(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_yaml(value)))
+                    .collect(),
+            )
+        }
+        Value::Sequence(sequence) => Value::Sequence(sequence.into_iter().map(sort_yaml).collect()),
+        other => other,
+    }
+}
+
+fn parse_key_path(key: &str) -> Vec<PathSegment> {
+    let mut segments = Vec::new();
+    for part in key.split('/') {
+        let name_end = part.find('[').unwrap_or(part.len());
+        segments.push(PathSegment::Key(part[..name_end].to_string()));
+
+        let mut rest = &part[name_end..];
+        while let Some(close) = rest.find(']') {
+            match rest[1..close].parse() {
+                Ok(index) => segments.push(PathSegment::Index(index)),
+                Err(_) => {
+                    // Not a sequence index, keep the brackets as part of the key
+                    if let Some(PathSegment::Key(name)) = segments.last_mut() {
+                        name.push_str(&rest[..=close]);
+                    }
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+    }
+    segments
+}
+
+fn insert_yaml(node: &mut Value, path: &[PathSegment], leaf: Value) {
+    let Some((segment, rest)) = path.split_first() else {
+        *node = leaf;
+        return;
+    };
+
+    match segment {
+        PathSegment::Key(key) => {
+            if !node.is_mapping() {
+                *node = Value::Mapping(Mapping::new());
+            }
+            let mapping = node.as_mapping_mut().unwrap();
+            let child = mapping
+                .entry(Value::String(key.clone()))
+                .or_insert(Value::Null);
+            insert_yaml(child, rest, leaf);
+        }
+        PathSegment::Index(index) => {
+            if !node.is_sequence() {
+                *node = Value::Sequence(Vec::new());
+            }
+            let sequence = node.as_sequence_mut().unwrap();
+            if sequence.len() <= *index {
+                sequence.resize(index + 1, Value::Null);
+            }
+            insert_yaml(&mut sequence[*index], rest, leaf);
+        }
+    }
+}
+
+fn string_to_value(value: &str) -> Value {
+    // Restores the scalars that value_to_string flattened into strings
+    match serde_yaml::from_str::<Value>(value) {
+        Ok(scalar @ (Value::Bool(_) | Value::Number(_))) => scalar,
+        _ if value == "null" => Value::Null,
+        _ => Value::String(value.to_string()),
+    }
+}
+
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Sequence(seq) => format!("{:?}", seq),
+        Value::Mapping(map) => format!("{:?}", map),
+        Value::Tagged(tagged) => format!("{:?}", tagged),
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use crate::{
+    cli::{ColorChoice, OutputFormat, SortOrder},
+    diff::DiffResult,
+    error::{Error, Result},
+    input::Locations,
+    output::{out, outln},
+};
+
+mod csv;
+mod github;
+mod html;
+mod inline;
+mod json;
+mod junit;
+mod kv_import;
+mod markdown;
+mod nested;
+mod script;
+mod table;
+mod template;
+mod terraform;
+mod text;
+mod unified;
+mod yaml;
+
+const GREEN: &str = "32";
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const CYAN: &str = "36";
+
+impl ColorChoice {
+    /// Whether to color what is printed to stdout. The automatic choice only
+    /// colors a terminal, and not when `NO_COLOR` is set.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        }
+    }
+}
+
+/// Wraps the text in the ANSI escapes of a color when coloring.
+fn paint(color: bool, code: &str, text: &str) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// A kind of difference, as the formats listing a row per key show it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    OnlyInFile,
+    OnlyInConsul,
+    Changed,
+}
+
+/// A differing key with its value on each side, empty on the side it is
+/// missing from.
+#[derive(Debug)]
+struct Row<'a> {
+    key: &'a str,
+    kind: Kind,
+    consul_value: &'a str,
+    file_value: &'a str,
+}
+
+/// The differences as rows, grouped by kind unless sorted by key.
+fn rows(result: &DiffResult, sort_order: SortOrder) -> Vec<Row<'_>> {
+    let mut rows: Vec<_> = result
+        .only_in_file
+        .iter()
+        .map(|item| Row {
+            key: &item.key,
+            kind: Kind::OnlyInFile,
+            consul_value: "",
+            file_value: &item.value,
+        })
+        .chain(result.only_in_consul.iter().map(|item| Row {
+            key: &item.key,
+            kind: Kind::OnlyInConsul,
+            consul_value: &item.value,
+            file_value: "",
+        }))
+        .chain(
+            result
+                .changed
+                .iter()
+                .map(|(key, file_value, consul_value)| Row {
+                    key,
+                    kind: Kind::Changed,
+                    consul_value,
+                    file_value,
+                }),
+        )
+        .collect();
+    if sort_order == SortOrder::Key {
+        rows.sort_by_key(|row| row.key);
+    }
+    rows
+}
+
+/// The full path of a key under an app prefix, or the key itself without
+/// one.
+fn full_key(app_prefix: &str, key: &str) -> String {
+    if app_prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}/{}", app_prefix, key)
+    }
+}
+
+/// The file values of the keys missing or changed in Consul, which is what
+/// Consul should be set to. With `full_state` the unchanged keys are
+/// included, for the formats that replace everything under a prefix.
+fn desired_values(
+    result: &DiffResult,
+    full_state: bool,
+) -> impl Iterator<Item = (&String, &String)> {
+    let unchanged = full_state
+        .then_some(&result.unchanged)
+        .into_iter()
+        .flatten();
+    unchanged
+        .chain(&result.only_in_file)
+        .map(|item| (&item.key, &item.value))
+        .chain(
+            result
+                .changed
+                .iter()
+                .map(|(key, file_value, _)| (key, file_value)),
+        )
+}
+
+/// Renders a diff result in the given output format, colored for a
+/// terminal if `color` is set and the format has colors. The formats listing
+/// a row per key mix the kinds of differences when sorting by key.
+pub fn render(
+    result: &DiffResult,
+    output_format: OutputFormat,
+    color: bool,
+    sort_order: SortOrder,
+) -> Result<String> {
+    match output_format {
+        OutputFormat::Text => Ok(text::render(result, color)),
+        OutputFormat::Json => serde_json::to_string_pretty(&json::render(result))
+            .map(|json| json + "\n")
+            .map_err(Error::serialize),
+        OutputFormat::Yaml => yaml::render(result),
+        OutputFormat::Diff => Ok(unified::render(result, color)),
+        OutputFormat::Table => Ok(table::render(result, color, sort_order)),
+        OutputFormat::Markdown => Ok(markdown::render(result, sort_order)),
+        OutputFormat::Csv => csv::render(result, sort_order),
+        OutputFormat::Html => Ok(html::render(result)),
+        OutputFormat::Junit | OutputFormat::Github => render_all(
+            &[(String::from("consul"), result.clone())],
+            output_format,
+            color,
+            sort_order,
+            false,
+            &Locations::new(),
+        ),
+        OutputFormat::Terraform | OutputFormat::Script | OutputFormat::KvImport => render_all(
+            &[(String::new(), result.clone())],
+            output_format,
+            color,
+            sort_order,
+            false,
+            &Locations::new(),
+        ),
+    }
+}
+
+/// The summary footer of a text report, counting the keys on each side and
+/// the differences by kind.
+pub fn summary(result: &DiffResult) -> String {
+    text::summary(result)
+}
+
+/// Renders the results of several app prefixes at once. JUnit reports keep
+/// a test suite per prefix, the formats that apply a fix use the full paths
+/// of the keys, with every key in the file when `full_state` is set, and
+/// GitHub annotations point at the `locations` of the keys in the input
+/// files, while the other formats list the differences of every prefix under
+/// their full keys.
+pub fn render_all(
+    results: &[(String, DiffResult)],
+    output_format: OutputFormat,
+    color: bool,
+    sort_order: SortOrder,
+    full_state: bool,
+    locations: &Locations,
+) -> Result<String> {
+    match (output_format, results) {
+        (OutputFormat::Junit, results) => Ok(junit::render(results)),
+        (OutputFormat::Github, results) => Ok(github::render(results, locations)),
+        (OutputFormat::Terraform, results) => Ok(terraform::render(results, full_state)),
+        (OutputFormat::KvImport, results) => {
+            serde_json::to_string_pretty(&kv_import::render(results, full_state))
+                .map(|json| json + "\n")
+                .map_err(Error::serialize)
+        }
+        (OutputFormat::Script, results) => Ok(script::render(results)),
+        (_, [(_, result)]) => render(result, output_format, color, sort_order),
+        (_, results) => {
+            let mut all_differences = DiffResult::default();
+            for (app_prefix, result) in results {
+                all_differences.extend(result.clone().with_prefix(app_prefix));
+            }
+            all_differences.sort(sort_order);
+            render(&all_differences, output_format, color, sort_order)
+        }
+    }
+}
+
+/// Renders the results of every app prefix through a user provided template.
+pub fn render_template(template_file: &Path, results: &[(String, DiffResult)]) -> Result<String> {
+    template::render(template_file, results)
+}
+
+/// Writes a rendered report to the output file, or to stdout without one.
+/// Scripts are made executable.
+pub fn write(
+    output_file: Option<&Path>,
+    output_format: OutputFormat,
+    contents: &str,
+) -> Result<()> {
+    match output_file {
+        Some(output_file) => {
+            fs::write(output_file, contents).map_err(|e| Error::write(output_file, e))?;
+            if output_format == OutputFormat::Script {
+                make_executable(output_file)?;
+            }
+            outln!("Diff written to {}.", output_file.display());
+        }
+        None => out!("{}", contents),
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(file_path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(file_path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| Error::write(file_path, e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_file_path: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use super::Kind;
+use crate::{
+    cli::SortOrder,
+    diff::DiffResult,
+    error::{Error, Result},
+};
+
+/// Renders the result as `key,status,consul_value,file_value` rows, quoting
+/// the values that hold commas, quotes or line breaks.
+pub fn render(result: &DiffResult, sort_order: SortOrder) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut record = |fields: [&str; 4]| writer.write_record(fields).map_err(Error::serialize);
+
+    record(["key", "status", "consul_value", "file_value"])?;
+    for row in super::rows(result, sort_order) {
+        let status = match row.kind {
+            Kind::OnlyInFile => "only_in_file",
+            Kind::OnlyInConsul => "only_in_consul",
+            Kind::Changed => "changed",
+        };
+        record([row.key, status, row.consul_value, row.file_value])?;
+    }
+
+    let bytes = writer.into_inner().map_err(Error::serialize)?;
+    String::from_utf8(bytes).map_err(Error::serialize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::SortOrder;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn renders_a_row_per_difference() {
+        let mut result = result();
+        result.changed[0].1 = "80,81".to_string();
+        assert_eq!(render(&result, SortOrder::Key).unwrap(), "key,status,consul_value,file_value\nname,only_in_file,,app\nold,only_in_consul,x,\nport,changed,8080,\"80,81\"\n");
+    }
+}
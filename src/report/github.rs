@@ -0,0 +1,67 @@
+use std::fmt::Write;
+
+use crate::{diff::DiffResult, input::Locations};
+
+/// Renders a GitHub Actions `::warning` workflow command per difference,
+/// pointing at the line of the input file that sets the key when it is
+/// known, so drift shows up inline in pull requests.
+pub fn render(results: &[(String, DiffResult)], locations: &Locations) -> String {
+    let mut text = String::new();
+    for (app_prefix, result) in results {
+        let mut annotate = |key: &str, message: String| {
+            let mut properties = Vec::new();
+            if let Some((file_path, line)) = locations.get(key) {
+                properties.push(format!(
+                    "file={}",
+                    escape_property(&file_path.display().to_string())
+                ));
+                properties.push(format!("line={}", line));
+            }
+            properties.push(format!(
+                "title={}",
+                escape_property(&format!("Configuration drift in {}", app_prefix))
+            ));
+            let _ = writeln!(
+                text,
+                "::warning {}::{}",
+                properties.join(","),
+                escape_data(&message)
+            );
+        };
+
+        for item in &result.only_in_file {
+            annotate(
+                &item.key,
+                format!("{} is missing in consul, file={:?}", item.key, item.value),
+            );
+        }
+        for item in &result.only_in_consul {
+            annotate(
+                &item.key,
+                format!("{} is only in consul, consul={:?}", item.key, item.value),
+            );
+        }
+        for (key, file_value, consul_value) in &result.changed {
+            annotate(
+                key,
+                format!(
+                    "{} differs, consul={:?} file={:?}",
+                    key, consul_value, file_value
+                ),
+            );
+        }
+    }
+    text
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command, which also ends at `:` or `,`.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,126 @@
+use std::fmt::Write;
+
+use super::inline::{self, Segment};
+use crate::{diff::DiffResult, properties::ConsulProperties};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+input { width: 100%; padding: .4em; margin-bottom: 1em; }
+summary { font-size: 1.2em; font-weight: bold; cursor: pointer; margin: .5em 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: .3em .6em; text-align: left; vertical-align: top; }
+td { font-family: monospace; white-space: pre-wrap; word-break: break-all; }
+del { background: #fdd; }
+ins { background: #dfd; text-decoration: none; }
+";
+
+/// Hides the rows whose text does not contain the search box text.
+const SCRIPT: &str = "\
+document.getElementById('search').addEventListener('input', function (event) {
+  var query = event.target.value.toLowerCase();
+  document.querySelectorAll('tbody tr').forEach(function (row) {
+    row.hidden = row.textContent.toLowerCase().indexOf(query) < 0;
+  });
+});
+";
+
+/// Renders the result as a standalone HTML page with a collapsible section
+/// per kind of difference, a search box filtering the keys and the changed
+/// values diffed character by character.
+pub fn render(result: &DiffResult) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Configuration drift</title>\n");
+    let _ = writeln!(html, "<style>\n{}</style>\n</head>\n<body>", STYLE);
+    html.push_str("<h1>Configuration drift</h1>\n");
+    let _ = writeln!(
+        html,
+        "<p>{} only in file, {} only in consul, {} changed, {} unchanged.</p>",
+        result.only_in_file.len(),
+        result.only_in_consul.len(),
+        result.changed.len(),
+        result.unchanged.len()
+    );
+    html.push_str("<input id=\"search\" type=\"search\" placeholder=\"Filter keys and values\">\n");
+
+    section(
+        &mut html,
+        "Only in file",
+        "File value",
+        &result.only_in_file,
+    );
+    section(
+        &mut html,
+        "Only in consul",
+        "Consul value",
+        &result.only_in_consul,
+    );
+
+    if !result.changed.is_empty() {
+        let _ = writeln!(
+            html,
+            "<details open>\n<summary>Changed ({})</summary>",
+            result.changed.len()
+        );
+        html.push_str("<table>\n<thead><tr><th>Key</th><th>Consul value</th><th>File value</th><th>Difference</th></tr></thead>\n<tbody>\n");
+        for (key, file_value, consul_value) in &result.changed {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(key),
+                escape(consul_value),
+                escape(file_value),
+                value_diff(consul_value, file_value)
+            );
+        }
+        html.push_str("</tbody>\n</table>\n</details>\n");
+    }
+
+    let _ = writeln!(html, "<script>\n{}</script>\n</body>\n</html>", SCRIPT);
+    html
+}
+
+fn section(html: &mut String, title: &str, value_title: &str, items: &[ConsulProperties]) {
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(
+        html,
+        "<details open>\n<summary>{} ({})</summary>",
+        title,
+        items.len()
+    );
+    let _ = writeln!(
+        html,
+        "<table>\n<thead><tr><th>Key</th><th>{}</th></tr></thead>\n<tbody>",
+        value_title
+    );
+    for item in items {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape(&item.key),
+            escape(&item.value)
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</details>\n");
+}
+
+/// Marks the characters removed from the Consul value and added in the file
+/// value.
+fn value_diff(consul_value: &str, file_value: &str) -> String {
+    inline::diff(consul_value, file_value)
+        .iter()
+        .map(|segment| match segment {
+            Segment::Same(text) => escape(text),
+            Segment::Removed(text) => format!("<del>{}</del>", escape(text)),
+            Segment::Added(text) => format!("<ins>{}</ins>", escape(text)),
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
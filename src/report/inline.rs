@@ -0,0 +1,84 @@
+/// Bounds the table of the character by character comparison.
+const MAX_INLINE_DIFF: usize = 1 << 20;
+
+/// A run of characters of a changed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Splits two values into the runs of characters kept, removed from `old`
+/// and added in `new`, around the longest common subsequence of both.
+pub fn diff(old: &str, new: &str) -> Vec<Segment> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let text = |chars: &[char]| chars.iter().collect::<String>();
+
+    // Values too long to compare character by character are shown as a
+    // single change between their common start and end
+    if old.len() * new.len() > MAX_INLINE_DIFF {
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        return [
+            Segment::Same(text(&old[..prefix])),
+            Segment::Removed(text(&old[prefix..old.len() - suffix])),
+            Segment::Added(text(&new[prefix..new.len() - suffix])),
+            Segment::Same(text(&old[old.len() - suffix..])),
+        ]
+        .into_iter()
+        .filter(|segment| !segment.text().is_empty())
+        .collect();
+    }
+
+    // lengths[i][j] is the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut segments: Vec<Segment> = Vec::new();
+    // Consecutive characters of the same kind join a single run
+    let mut push = |segment: Segment| match (segments.last_mut(), segment) {
+        (Some(Segment::Same(run)), Segment::Same(text))
+        | (Some(Segment::Removed(run)), Segment::Removed(text))
+        | (Some(Segment::Added(run)), Segment::Added(text)) => run.push_str(&text),
+        (_, segment) => segments.push(segment),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(Segment::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+            push(Segment::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push(Segment::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    segments
+}
+
+impl Segment {
+    pub fn text(&self) -> &str {
+        match self {
+            Segment::Same(text) | Segment::Removed(text) | Segment::Added(text) => text,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::{json, Value};
+
+use crate::{diff::DiffResult, properties::ConsulProperties};
+
+/// Lays out the result as an object with one array per kind of difference.
+pub fn render(result: &DiffResult) -> Value {
+    let pairs = |items: &[ConsulProperties]| -> Vec<Value> {
+        items
+            .iter()
+            .map(|item| json!({ "key": item.key, "value": item.value }))
+            .collect()
+    };
+
+    json!({
+        "only_in_file": pairs(&result.only_in_file),
+        "only_in_consul": pairs(&result.only_in_consul),
+        "changed": result
+            .changed
+            .iter()
+            .map(|(key, file_value, consul_value)| json!({
+                "key": key,
+                "consul_value": consul_value,
+                "file_value": file_value,
+            }))
+            .collect::<Vec<_>>(),
+        "renamed": result
+            .renamed
+            .iter()
+            .map(|(consul_key, file_key, value)| json!({
+                "consul_key": consul_key,
+                "file_key": file_key,
+                "value": value,
+            }))
+            .collect::<Vec<_>>(),
+        "unresolvable": result
+            .unresolvable
+            .iter()
+            .map(|(key, file_value, consul_value)| json!({
+                "key": key,
+                "consul_value": consul_value,
+                "file_value": file_value,
+            }))
+            .collect::<Vec<_>>(),
+        "empty_in_consul": result
+            .empty_in_consul
+            .iter()
+            .map(|(key, file_value)| json!({ "key": key, "file_value": file_value }))
+            .collect::<Vec<_>>(),
+        "ignored": result
+            .ignored
+            .iter()
+            .map(|change| json!({ "key": change.key() }))
+            .collect::<Vec<_>>(),
+        "case_mismatches": result
+            .case_mismatches
+            .iter()
+            .map(|(consul_key, file_key)| json!({
+                "consul_key": consul_key,
+                "file_key": file_key,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn lays_out_each_kind_of_difference() {
+        let value = render(&result());
+        assert_eq!(
+            value["only_in_file"],
+            json!([{"key": "name", "value": "app"}])
+        );
+        assert_eq!(
+            value["only_in_consul"],
+            json!([{"key": "old", "value": "x"}])
+        );
+        assert_eq!(
+            value["changed"],
+            json!([{"key": "port", "consul_value": "8080", "file_value": "8081"}])
+        );
+        assert_eq!(value["renamed"], json!([]));
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt::Write;
+
+use crate::diff::DiffResult;
+
+/// Renders a JUnit test suite per app prefix, with a passing test case for
+/// every unchanged key and a failing one for every difference, so CI servers
+/// show drift in their test reports.
+pub fn render(results: &[(String, DiffResult)]) -> String {
+    let tests: usize = results.iter().map(|(_, result)| cases(result).len()).sum();
+    let failures: usize = results.iter().map(|(_, result)| result.len()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"consul-help\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    );
+    for (app_prefix, result) in results {
+        let cases = cases(result);
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+            escape(app_prefix),
+            cases.len(),
+            result.len()
+        );
+        for (key, failure) in cases {
+            match failure {
+                None => {
+                    let _ = writeln!(
+                        xml,
+                        "    <testcase classname=\"{}\" name=\"{}\"/>",
+                        escape(app_prefix),
+                        escape(key)
+                    );
+                }
+                Some((kind, details)) => {
+                    let _ = writeln!(
+                        xml,
+                        "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>",
+                        escape(app_prefix),
+                        escape(key),
+                        kind,
+                        kind.replace('_', " "),
+                        escape(&details)
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Every key of a result sorted, with the kind and details of its
+/// difference if it has one.
+fn cases(result: &DiffResult) -> Vec<(&str, Option<(&'static str, String)>)> {
+    let mut cases: Vec<_> = result
+        .unchanged
+        .iter()
+        .map(|item| (item.key.as_str(), None))
+        .chain(result.only_in_file.iter().map(|item| {
+            let details = format!("file={:?}", item.value);
+            (item.key.as_str(), Some(("only_in_file", details)))
+        }))
+        .chain(result.only_in_consul.iter().map(|item| {
+            let details = format!("consul={:?}", item.value);
+            (item.key.as_str(), Some(("only_in_consul", details)))
+        }))
+        .chain(
+            result
+                .changed
+                .iter()
+                .map(|(key, file_value, consul_value)| {
+                    let details = format!("consul={:?} file={:?}", consul_value, file_value);
+                    (key.as_str(), Some(("changed", details)))
+                }),
+        )
+        .collect();
+    cases.sort_by_key(|(key, _)| *key);
+    cases
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn fails_a_test_case_per_difference() {
+        let xml = render(&[("config/app".to_string(), result())]);
+        assert!(xml.contains("<testsuite name=\"config/app\" tests=\"4\" failures=\"3\">"));
+        assert!(xml.contains("<testcase classname=\"config/app\" name=\"host\"/>"));
+        assert!(xml.contains(
+            "<failure type=\"changed\" message=\"changed\">consul=&quot;8080&quot; file=&quot;8081&quot;</failure>"
+        ));
+    }
+}
@@ -0,0 +1,23 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+
+use super::{desired_values, full_key};
+use crate::diff::DiffResult;
+
+/// Lays out the file values of the keys missing or changed in Consul, or of
+/// every key in the file with `full_state`, as the array of `key`, `flags`
+/// and base64 `value` objects that `consul kv import` reads.
+pub fn render(results: &[(String, DiffResult)], full_state: bool) -> Value {
+    results
+        .iter()
+        .flat_map(|(app_prefix, result)| {
+            desired_values(result, full_state).map(move |(key, value)| {
+                json!({
+                    "key": full_key(app_prefix, key),
+                    "flags": 0,
+                    "value": STANDARD.encode(value),
+                })
+            })
+        })
+        .collect()
+}
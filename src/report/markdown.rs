@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use super::Kind;
+use crate::{cli::SortOrder, diff::DiffResult};
+
+/// Renders the result as a Markdown summary of the counts per kind of
+/// difference followed by a table of the differing keys.
+pub fn render(result: &DiffResult, sort_order: SortOrder) -> String {
+    let mut text = String::from("## Configuration drift\n\n");
+    text.push_str("| | Keys |\n| --- | ---: |\n");
+    let _ = writeln!(text, "| Only in file | {} |", result.only_in_file.len());
+    let _ = writeln!(text, "| Only in consul | {} |", result.only_in_consul.len());
+    let _ = writeln!(text, "| Changed | {} |", result.changed.len());
+    let _ = writeln!(text, "| Unchanged | {} |", result.unchanged.len());
+    text.push('\n');
+
+    if result.is_empty() {
+        text.push_str("No differences found.\n");
+        return text;
+    }
+
+    text.push_str("| Key | Consul value | File value | Status |\n");
+    text.push_str("| --- | --- | --- | --- |\n");
+    for row in super::rows(result, sort_order) {
+        let status = match row.kind {
+            Kind::OnlyInFile => "only in file",
+            Kind::OnlyInConsul => "only in consul",
+            Kind::Changed => "changed",
+        };
+        let _ = writeln!(
+            text,
+            "| {} | {} | {} | {} |",
+            code(row.key),
+            code(row.consul_value),
+            code(row.file_value),
+            status
+        );
+    }
+    text
+}
+
+/// Formats a key or value as inline code, which keeps Markdown from
+/// interpreting it. A value can only hold a pipe or a line break escaped.
+fn code(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let text = text.replace('|', "\\|").replace('\n', "<br>");
+    // A fence longer than any run of backticks in the text encloses it
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", fence, text, fence)
+    } else {
+        format!("{}{}{}", fence, text, fence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::SortOrder;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn renders_the_counts_and_a_table_of_differences() {
+        assert_eq!(render(&result(), SortOrder::Key), "## Configuration drift\n\n| | Keys |\n| --- | ---: |\n| Only in file | 1 |\n| Only in consul | 1 |\n| Changed | 1 |\n| Unchanged | 1 |\n\n| Key | Consul value | File value | Status |\n| --- | --- | --- | --- |\n| `name` |  | `app` | only in file |\n| `old` | `x` |  | only in consul |\n| `port` | `8080` | `8081` | changed |\n");
+    }
+
+    #[test]
+    fn escapes_pipes_and_backticks_in_code() {
+        assert_eq!(code("a|b"), "`a\\|b`");
+        assert_eq!(code("a`b"), "``a`b``");
+        assert_eq!(code("`a"), "`` `a ``");
+        assert_eq!(code(""), "");
+    }
+}
@@ -0,0 +1,54 @@
+use serde_json::Value;
+
+/// A member of a JSON document that differs, by its path from the root such
+/// as `$.datasource.hosts[1]`, with its value on each side it is set on.
+#[derive(Debug)]
+pub struct Difference<'a> {
+    pub path: String,
+    pub old: Option<&'a Value>,
+    pub new: Option<&'a Value>,
+}
+
+/// Walks two JSON documents together and lists the members that differ,
+/// down to the innermost ones. Objects are compared member by member and
+/// arrays element by element.
+pub fn diff<'a>(old: &'a Value, new: &'a Value) -> Vec<Difference<'a>> {
+    let mut differences = Vec::new();
+    walk("$".to_string(), Some(old), Some(new), &mut differences);
+    differences
+}
+
+fn walk<'a>(
+    path: String,
+    old: Option<&'a Value>,
+    new: Option<&'a Value>,
+    differences: &mut Vec<Difference<'a>>,
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let mut keys: Vec<_> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                walk(
+                    format!("{}.{}", path, key),
+                    old.get(key),
+                    new.get(key),
+                    differences,
+                );
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                walk(
+                    format!("{}[{}]", path, index),
+                    old.get(index),
+                    new.get(index),
+                    differences,
+                );
+            }
+        }
+        (old, new) if old != new => differences.push(Difference { path, old, new }),
+        _ => {}
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt::Write;
+
+use super::{desired_values, full_key};
+use crate::diff::DiffResult;
+
+/// Renders a shell script of `consul kv put` commands setting the keys
+/// missing or changed in Consul to their file values, so the fix can be
+/// applied with the official CLI alone. Deleting the keys only in Consul is
+/// left commented out.
+pub fn render(results: &[(String, DiffResult)]) -> String {
+    let mut script = String::from("#!/bin/sh\nset -e\n");
+    for (app_prefix, result) in results {
+        if result.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(script, "\n# {}", app_prefix);
+        for (key, value) in desired_values(result, false) {
+            let key = quote(&full_key(app_prefix, key));
+            // consul kv put reads `-` from stdin and `@path` from a file
+            if value == "-" || value.starts_with('@') {
+                let _ = writeln!(
+                    script,
+                    "printf '%s' {} | consul kv put {} -",
+                    quote(value),
+                    key
+                );
+            } else {
+                let _ = writeln!(script, "consul kv put {} {}", key, quote(value));
+            }
+        }
+        for item in &result.only_in_consul {
+            let _ = writeln!(
+                script,
+                "# consul kv delete {}",
+                quote(&full_key(app_prefix, &item.key))
+            );
+        }
+    }
+    script
+}
+
+/// Quotes a shell word in single quotes, which keep everything literal
+/// except a single quote itself.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn puts_the_file_values_and_comments_out_deletes() {
+        let mut result = result();
+        result.only_in_file.push(property("quote", "it's"));
+        result.only_in_file.push(property("stdin", "-"));
+        assert_eq!(render(&[("config/app".to_string(), result)]), "#!/bin/sh\nset -e\n\n# config/app\nconsul kv put 'config/app/name' 'app'\nconsul kv put 'config/app/quote' 'it'\\''s'\nprintf '%s' '-' | consul kv put 'config/app/stdin' -\nconsul kv put 'config/app/port' '8081'\n# consul kv delete 'config/app/old'\n");
+    }
+}
@@ -0,0 +1,105 @@
+use std::env;
+
+use super::{paint, Kind, GREEN, RED, YELLOW};
+use crate::{cli::SortOrder, diff::DiffResult};
+
+/// Terminal width assumed when `COLUMNS` does not say otherwise.
+const DEFAULT_WIDTH: usize = 120;
+
+/// Columns are not truncated below this many characters.
+const MIN_COLUMN: usize = 8;
+
+const GAP: &str = "  ";
+
+/// Renders the result as aligned key, Consul value, file value and status
+/// columns. The widest of the first three columns is truncated until the
+/// table fits the terminal width, taken from `COLUMNS`.
+pub fn render(result: &DiffResult, color: bool, sort_order: SortOrder) -> String {
+    let mut rows: Vec<[String; 4]> = super::rows(result, sort_order)
+        .into_iter()
+        .map(|row| {
+            let status = match row.kind {
+                Kind::OnlyInFile => "only in file",
+                Kind::OnlyInConsul => "only in consul",
+                Kind::Changed => "changed",
+            };
+            [row.key, row.consul_value, row.file_value, status].map(String::from)
+        })
+        .collect();
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    // Line breaks would break the alignment
+    for row in &mut rows {
+        for cell in row.iter_mut() {
+            *cell = cell.replace('\n', "\\n");
+        }
+    }
+
+    let header = ["KEY", "CONSUL VALUE", "FILE VALUE", "STATUS"].map(String::from);
+    let mut widths = [0; 4];
+    for row in rows.iter().chain([&header]) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let available = env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_WIDTH);
+    let total = |widths: &[usize; 4]| widths.iter().sum::<usize>() + GAP.len() * 3;
+    while total(&widths) > available {
+        let widest = (0..3).max_by_key(|&column| widths[column]).unwrap_or(0);
+        if widths[widest] <= MIN_COLUMN {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+
+    let mut text = format_row(&header, &widths, None, color);
+    for row in &rows {
+        let status = match row[3].as_str() {
+            "only in file" => GREEN,
+            "only in consul" => RED,
+            _ => YELLOW,
+        };
+        text.push_str(&format_row(row, &widths, Some(status), color));
+    }
+    text
+}
+
+fn format_row(row: &[String; 4], widths: &[usize; 4], status: Option<&str>, color: bool) -> String {
+    let cells: Vec<String> = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| pad(&truncate(cell, *width), *width))
+        .collect();
+    let last = match status {
+        Some(code) => paint(color, code, cells[3].trim_end()),
+        None => cells[3].trim_end().to_string(),
+    };
+    format!(
+        "{}{GAP}{}{GAP}{}{GAP}{}\n",
+        cells[0], cells[1], cells[2], last
+    )
+}
+
+/// Shortens a cell to the width, marking the cut with an ellipsis.
+fn truncate(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+    let mut truncated: String = cell.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn pad(cell: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        cell,
+        " ".repeat(width.saturating_sub(cell.chars().count()))
+    )
+}
@@ -0,0 +1,67 @@
+use serde_json::{json, Value};
+use std::{error::Error as _, fs, path::Path};
+use tera::{Context, Tera};
+
+use super::json;
+use crate::{
+    diff::DiffResult,
+    error::{Error, Result},
+};
+
+/// Renders the results through a Tera template. The template sees the
+/// `only_in_file`, `only_in_consul`, `changed` and `unchanged` arrays of the
+/// JSON format for every prefix together under their full keys, and the
+/// same arrays of each prefix with its `app_prefix` in `results`. Templates
+/// named like HTML or XML files get their values escaped.
+pub fn render(template_file: &Path, results: &[(String, DiffResult)]) -> Result<String> {
+    let template = fs::read_to_string(template_file).map_err(|e| Error::read(template_file, e))?;
+    let name = template_file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut tera = Tera::default();
+    tera.add_raw_template(&name, &template)
+        .map_err(|e| Error::read(template_file, causes(&e)))?;
+
+    let mut all_differences = DiffResult::default();
+    for (app_prefix, result) in results {
+        all_differences.extend(result.clone().with_prefix(app_prefix));
+    }
+    let mut context = fields(&all_differences);
+    context["results"] = results
+        .iter()
+        .map(|(app_prefix, result)| {
+            let mut fields = fields(result);
+            fields["app_prefix"] = json!(app_prefix);
+            fields
+        })
+        .collect();
+
+    let context = Context::from_value(context).map_err(Error::serialize)?;
+    tera.render(&name, &context)
+        .map_err(|e| Error::read(template_file, causes(&e)))
+}
+
+/// The JSON arrays of a result, with the unchanged keys as well.
+fn fields(result: &DiffResult) -> Value {
+    let mut fields = json::render(result);
+    fields["unchanged"] = result
+        .unchanged
+        .iter()
+        .map(|item| json!({ "key": item.key, "value": item.value }))
+        .collect();
+    fields
+}
+
+/// A Tera error with its causes, which hold what went wrong and where.
+fn causes(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
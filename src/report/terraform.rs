@@ -0,0 +1,101 @@
+use std::fmt::Write;
+
+use super::{desired_values, full_key};
+use crate::diff::DiffResult;
+
+/// Renders a Terraform `consul_keys` resource per app prefix that sets the
+/// keys missing or changed in Consul to their file values, so drift is fixed
+/// through the infrastructure code managing the KV store. A `consul_keys`
+/// resource never deletes the keys it does not manage, so the keys only in
+/// Consul are listed as comments to remove by hand. With `full_state` a
+/// `consul_key_prefix` resource holds every key in the file instead, and
+/// makes Terraform delete the others.
+pub fn render(results: &[(String, DiffResult)], full_state: bool) -> String {
+    let mut hcl = String::new();
+    for (app_prefix, result) in results {
+        if result.is_empty() && !full_state {
+            continue;
+        }
+        if !hcl.is_empty() {
+            hcl.push('\n');
+        }
+
+        if full_state {
+            let _ = writeln!(
+                hcl,
+                "resource \"consul_key_prefix\" \"{}\" {{\n  path_prefix = \"{}\"\n\n  subkeys = {{",
+                resource_name(app_prefix),
+                escape(&full_key(app_prefix, ""))
+            );
+            for (key, value) in desired_values(result, true) {
+                let _ = writeln!(hcl, "    \"{}\" = \"{}\"", escape(key), escape(value));
+            }
+            hcl.push_str("  }\n}\n");
+            continue;
+        }
+
+        let _ = writeln!(
+            hcl,
+            "resource \"consul_keys\" \"{}\" {{",
+            resource_name(app_prefix)
+        );
+        for (key, value) in desired_values(result, false) {
+            let _ = writeln!(
+                hcl,
+                "  key {{\n    path  = \"{}\"\n    value = \"{}\"\n  }}",
+                escape(&full_key(app_prefix, key)),
+                escape(value)
+            );
+        }
+        for item in &result.only_in_consul {
+            let _ = writeln!(
+                hcl,
+                "  # only in consul: {}",
+                full_key(app_prefix, &item.key)
+            );
+        }
+        hcl.push_str("}\n");
+    }
+    hcl
+}
+
+/// A resource name from the app prefix, which may only hold letters,
+/// digits, underscores and dashes and must not start with a digit.
+fn resource_name(app_prefix: &str) -> String {
+    let name: String = app_prefix
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => name,
+        _ => format!("_{}", name),
+    }
+}
+
+/// Escapes a quoted HCL string, including the `${` and `%{` sequences that
+/// would otherwise start an interpolation or a directive.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '$' | '%' if chars.peek() == Some(&'{') => {
+                escaped.push(c);
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,243 @@
+use std::fmt::Write;
+
+use super::{
+    inline::{self, Segment},
+    nested::{self, Difference},
+    paint, GREEN, RED, YELLOW,
+};
+use crate::diff::{json_document, Change, DiffResult};
+
+/// Changed values longer than this many characters also get an inline diff.
+const LONG_VALUE: usize = 40;
+
+/// Lists each kind of difference under its own heading, in green for the
+/// keys only in the file, red for the keys only in Consul and yellow for the
+/// changed and renamed ones when colored, then the unresolvable, empty and
+/// ignored keys and the keys whose casing differs. Changed JSON documents
+/// list the members that differ.
+pub fn render(result: &DiffResult, color: bool) -> String {
+    let mut text = String::new();
+    if !result.only_in_file.is_empty() {
+        text.push_str("Only in file:\n");
+        for item in &result.only_in_file {
+            let line = format!("{}={}", item.key, item.value);
+            let _ = writeln!(text, "  {}", paint(color, GREEN, &line));
+        }
+    }
+    if !result.only_in_consul.is_empty() {
+        text.push_str("Only in consul:\n");
+        for item in &result.only_in_consul {
+            let line = format!("{}={}", item.key, item.value);
+            let _ = writeln!(text, "  {}", paint(color, RED, &line));
+        }
+    }
+    if !result.changed.is_empty() {
+        text.push_str("Changed:\n");
+        for (key, file_value, consul_value) in &result.changed {
+            let line = format!("{}: consul={:?} file={:?}", key, consul_value, file_value);
+            let _ = writeln!(text, "  {}", paint(color, YELLOW, &line));
+            if let (Some(consul_document), Some(file_document)) =
+                (json_document(consul_value), json_document(file_value))
+            {
+                for difference in nested::diff(&consul_document, &file_document) {
+                    let _ = writeln!(text, "    {}", nested_line(&difference, color));
+                }
+            } else if consul_value.chars().count().max(file_value.chars().count()) > LONG_VALUE {
+                let _ = writeln!(text, "    {}", inline_diff(consul_value, file_value, color));
+            }
+        }
+    }
+    if !result.renamed.is_empty() {
+        text.push_str("Renamed:\n");
+        for (consul_key, file_key, value) in &result.renamed {
+            let line = format!("{} -> {}={}", consul_key, file_key, value);
+            let _ = writeln!(text, "  {}", paint(color, YELLOW, &line));
+        }
+    }
+    if !result.unresolvable.is_empty() {
+        text.push_str("Unresolvable:\n");
+        for (key, file_value, consul_value) in &result.unresolvable {
+            let _ = writeln!(
+                text,
+                "  {}: consul={:?} file={:?}",
+                key, consul_value, file_value
+            );
+        }
+    }
+    if !result.empty_in_consul.is_empty() {
+        text.push_str("Empty in consul:\n");
+        for (key, file_value) in &result.empty_in_consul {
+            match file_value {
+                Some(file_value) => {
+                    let _ = writeln!(text, "  {}: file={:?}", key, file_value);
+                }
+                None => {
+                    let _ = writeln!(text, "  {}", key);
+                }
+            }
+        }
+    }
+    if !result.ignored.is_empty() {
+        text.push_str("Ignored:\n");
+        for change in &result.ignored {
+            let kind = match change {
+                Change::OnlyInFile(_) => "only in file",
+                Change::OnlyInConsul(_) => "only in consul",
+                Change::Changed { .. } => "changed",
+            };
+            let _ = writeln!(text, "  {} ({})", change.key(), kind);
+        }
+    }
+    if !result.case_mismatches.is_empty() {
+        text.push_str("Warnings:\n");
+        for (consul_key, file_key) in &result.case_mismatches {
+            let line = format!(
+                "{}: key casing differs from {} in file",
+                consul_key, file_key
+            );
+            let _ = writeln!(text, "  {}", paint(color, YELLOW, &line));
+        }
+    }
+    text
+}
+
+/// Counts the keys on each side and the differences by kind, with how much
+/// of the configuration is in sync.
+pub fn summary(result: &DiffResult) -> String {
+    let renamed = match result.renamed.len() {
+        0 => String::new(),
+        count => format!(", {} renamed", count),
+    };
+    let unresolvable = match result.unresolvable.len() {
+        0 => String::new(),
+        count => format!(", {} unresolvable", count),
+    };
+    let empty = match result.empty_in_consul.len() {
+        0 => String::new(),
+        count => format!(", {} empty in consul", count),
+    };
+    let ignored = match result.ignored.len() {
+        0 => String::new(),
+        count => format!(", {} ignored", count),
+    };
+    format!(
+        "{} keys in consul, {} keys in file: {} added, {} removed, {} changed{}{}{}{}, {:.1}% in sync.\n",
+        result.consul_len(),
+        result.file_len(),
+        result.only_in_file.len(),
+        result.only_in_consul.len(),
+        result.changed.len(),
+        renamed,
+        unresolvable,
+        empty,
+        ignored,
+        result.in_sync_percent()
+    )
+}
+
+/// Describes a member of a changed JSON document, in red when it is only in
+/// Consul and green when it is only in the file.
+fn nested_line(difference: &Difference, color: bool) -> String {
+    match (difference.old, difference.new) {
+        (Some(old), Some(new)) => format!("{}: consul={} file={}", difference.path, old, new),
+        (Some(old), None) => paint(color, RED, &format!("-{}={}", difference.path, old)),
+        (None, Some(new)) => paint(color, GREEN, &format!("+{}={}", difference.path, new)),
+        (None, None) => difference.path.clone(),
+    }
+}
+
+/// Marks the characters removed from `old` as `[-...-]` and the ones added
+/// in `new` as `{+...+}`, the way `git diff --word-diff` does. Colored,
+/// removals are red and additions green.
+fn inline_diff(old: &str, new: &str, color: bool) -> String {
+    inline::diff(old, new)
+        .iter()
+        .map(|segment| match segment {
+            Segment::Same(text) => text.clone(),
+            Segment::Removed(text) => paint(color, RED, &format!("[-{}-]", text)),
+            Segment::Added(text) => paint(color, GREEN, &format!("{{+{}+}}", text)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn changed(key: &str, file_value: &str, consul_value: &str) -> (String, String, String) {
+        (
+            key.to_string(),
+            file_value.to_string(),
+            consul_value.to_string(),
+        )
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![changed("port", "8081", "8080")],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn lists_each_kind_of_difference() {
+        assert_eq!(
+            render(&result(), false),
+            "Only in file:\n  name=app\nOnly in consul:\n  old=x\nChanged:\n  port: consul=\"8080\" file=\"8081\"\n"
+        );
+        assert!(render(&result(), true).contains("\x1b[32mname=app\x1b[0m"));
+        assert_eq!(render(&DiffResult::default(), false), "");
+    }
+
+    #[test]
+    fn counts_the_keys_and_differences() {
+        assert_eq!(
+            summary(&result()),
+            "3 keys in consul, 3 keys in file: 1 added, 1 removed, 1 changed, 25.0% in sync.\n"
+        );
+    }
+
+    #[test]
+    fn lists_the_members_of_changed_documents() {
+        let result = DiffResult {
+            changed: vec![changed("doc", r#"{"a": 1, "c": 3}"#, r#"{"a": 2, "b": 2}"#)],
+            ..DiffResult::default()
+        };
+        assert!(
+            render(&result, false).ends_with("    $.a: consul=2 file=1\n    -$.b=2\n    +$.c=3\n")
+        );
+    }
+
+    #[test]
+    fn marks_the_characters_that_differ_in_long_values() {
+        let same = "a".repeat(40);
+        let result = DiffResult {
+            changed: vec![changed(
+                "long",
+                &format!("{}new", same),
+                &format!("{}old", same),
+            )],
+            ..DiffResult::default()
+        };
+        assert!(render(&result, false).ends_with(&format!("    {}[-old-]{{+new+}}\n", same)));
+        assert!(!render(
+            &DiffResult {
+                changed: vec![changed("short", "new", "old")],
+                ..DiffResult::default()
+            },
+            false
+        )
+        .contains("[-"));
+    }
+}
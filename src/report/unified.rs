@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use super::{paint, CYAN, GREEN, RED};
+use crate::diff::DiffResult;
+
+/// Unchanged keys shown around each change, as `diff -u` does.
+const CONTEXT: usize = 3;
+
+/// Renders the result as a unified diff from the Consul keys to the file
+/// keys, sorted by key, with one `key=value` line per key. Colored, added
+/// lines are green, removed ones red and hunk headers cyan.
+pub fn render(result: &DiffResult, color: bool) -> String {
+    let mut lines: Vec<(&str, char, String)> = Vec::new();
+    for item in &result.unchanged {
+        lines.push((&item.key, ' ', format!("{}={}", item.key, item.value)));
+    }
+    for item in &result.only_in_consul {
+        lines.push((&item.key, '-', format!("{}={}", item.key, item.value)));
+    }
+    for item in &result.only_in_file {
+        lines.push((&item.key, '+', format!("{}={}", item.key, item.value)));
+    }
+    for (key, file_value, consul_value) in &result.changed {
+        lines.push((key, '-', format!("{}={}", key, consul_value)));
+        lines.push((key, '+', format!("{}={}", key, file_value)));
+    }
+    // The sort is stable, so a removed value stays before the added one
+    lines.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut text = String::new();
+    if result.is_empty() {
+        return text;
+    }
+    text.push_str("--- consul\n+++ file\n");
+
+    // Groups the changed lines that are close enough to share their context
+    let changed: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].1 != ' ').collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &position in &changed {
+        let start = position.saturating_sub(CONTEXT);
+        let end = (position + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let count = |range: &[(&str, char, String)], side: char| {
+            range
+                .iter()
+                .filter(|line| line.1 == ' ' || line.1 == side)
+                .count()
+        };
+        let (old_before, new_before) = (count(&lines[..start], '-'), count(&lines[..start], '+'));
+        let (old_length, new_length) = (
+            count(&lines[start..end], '-'),
+            count(&lines[start..end], '+'),
+        );
+        // An empty side starts at the line before the hunk
+        let old_start = if old_length == 0 {
+            old_before
+        } else {
+            old_before + 1
+        };
+        let new_start = if new_length == 0 {
+            new_before
+        } else {
+            new_before + 1
+        };
+
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_length, new_start, new_length
+        );
+        let _ = writeln!(text, "{}", paint(color, CYAN, &header));
+        for (_, tag, line) in &lines[start..end] {
+            let line = format!("{}{}", tag, line);
+            let line = match tag {
+                '+' => paint(color, GREEN, &line),
+                '-' => paint(color, RED, &line),
+                _ => line,
+            };
+            let _ = writeln!(text, "{}", line);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::ConsulProperties;
+
+    fn property(key: &str, value: &str) -> ConsulProperties {
+        ConsulProperties {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn result() -> DiffResult {
+        DiffResult {
+            only_in_file: vec![property("name", "app")],
+            only_in_consul: vec![property("old", "x")],
+            changed: vec![("port".to_string(), "8081".to_string(), "8080".to_string())],
+            unchanged: vec![property("host", "localhost")],
+            ..DiffResult::default()
+        }
+    }
+
+    #[test]
+    fn renders_a_hunk_from_consul_to_the_file() {
+        assert_eq!(render(&result(), false), "--- consul\n+++ file\n@@ -1,3 +1,3 @@\n host=localhost\n+name=app\n-old=x\n-port=8080\n+port=8081\n");
+        assert_eq!(render(&DiffResult::default(), false), "");
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{
+    diff::DiffResult,
+    error::{Error, Result},
+    properties::{self, ConsulProperties},
+};
+
+/// Nests the file values of the keys missing or changed in Consul into a
+/// YAML fragment, ready to paste into a config file or a FILES style key.
+/// The keys only in Consul have no file value and are listed in comments.
+pub fn render(result: &DiffResult) -> Result<String> {
+    let mut text = String::new();
+    for item in &result.only_in_consul {
+        text.push_str(&format!("# only in consul: {}={}\n", item.key, item.value));
+    }
+
+    let file_side: Vec<_> = result
+        .only_in_file
+        .iter()
+        .cloned()
+        .chain(
+            result
+                .changed
+                .iter()
+                .map(|(key, file_value, _)| ConsulProperties {
+                    key: key.clone(),
+                    value: file_value.clone(),
+                }),
+        )
+        .collect();
+    if !file_side.is_empty() {
+        let yaml = properties::unflatten_yaml(&file_side);
+        text.push_str(&serde_yaml::to_string(&yaml).map_err(Error::serialize)?);
+    }
+    Ok(text)
+}
@@ -0,0 +1,51 @@
+use clap::ValueEnum;
+use regex::Regex;
+use std::{fs, path::Path};
+
+use crate::{
+    cli::Severity,
+    error::{Error, Result},
+    pattern,
+};
+
+/// A line of a severity file, giving the keys it matches a severity.
+#[derive(Debug, Clone)]
+pub struct SeverityRule {
+    key: Regex,
+    severity: Severity,
+}
+
+/// The severity of the first rule matching a key, or info for the keys no
+/// rule matches.
+pub fn classify(rules: &[SeverityRule], key: &str) -> Severity {
+    rules
+        .iter()
+        .find(|rule| rule.key.is_match(key))
+        .map_or(Severity::Info, |rule| rule.severity)
+}
+
+/// Reads the rules of a severity file, one `PATTERN = SEVERITY` per line,
+/// such as `datasource/** = critical`, where the pattern is a glob or a
+/// `re:` regex. Blank lines and `#` comments are skipped.
+pub fn load(severity_file: Option<&Path>) -> Result<Vec<SeverityRule>> {
+    let Some(file_path) = severity_file else {
+        return Ok(Vec::new());
+    };
+    let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+
+    let mut rules = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_error = |e: String| Error::read(file_path, format!("line {}: {}", number + 1, e));
+        let Some((key, severity)) = line.rsplit_once('=') else {
+            return Err(line_error("expected PATTERN = SEVERITY".to_string()));
+        };
+        let key = pattern::parse_key_pattern(key.trim()).map_err(line_error)?;
+        let severity = Severity::from_str(severity.trim(), true).map_err(line_error)?;
+        rules.push(SeverityRule { key, severity });
+    }
+    Ok(rules)
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::{
+    error::{Error, Result},
+    output::progress,
+};
+
+/// The contents of a KV prefix at a point in time, as written by the
+/// snapshot command and read back by restore.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub consul_host: String,
+    pub app_prefix: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Key relative to the app prefix
+    pub key: String,
+    pub value: String,
+    pub flags: u64,
+    pub modify_index: u64,
+}
+
+impl Snapshot {
+    pub fn load(file_path: &PathBuf) -> Result<Snapshot> {
+        progress!("Loading snapshot from file: {}", file_path.display());
+
+        let contents = fs::read_to_string(file_path).map_err(|e| Error::read(file_path, e))?;
+        serde_json::from_str(&contents).map_err(|e| Error::read(file_path, e))
+    }
+
+    pub fn save(&self, file_path: &PathBuf) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(Error::serialize)?;
+        fs::write(file_path, contents).map_err(|e| Error::write(file_path, e))
+    }
+}